@@ -1,8 +1,12 @@
 /// Day 5: If You Give A Seed A Fertilizer, Part 2
-use std::ops::Range;
+use std::{env, ops::Range};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 fn main() {
     let input = include_str!("../../input.txt");
+    threads::configure(&env::args().collect::<Vec<_>>());
     println!("{}", get_min_location(input));
 }
 
@@ -23,16 +27,14 @@ fn get_min_location(input: &str) -> i64 {
     println!("Seeds started");
     let maps: [Vec<(i64, i64, i64)>; 7] = create_maps(&parts[1..]);
     println!("Maps created");
-    let mut minimum = -1;
-    for seed_range in seed_ranges {
-        for seed in seed_range {
-            let location = get_seed_location(seed, &maps);
-            if minimum == -1 || location < minimum {
-                minimum = location;
-            }
-        }
-    }
-    minimum
+    #[cfg(not(feature = "parallel"))]
+    let seeds = seed_ranges.into_iter().flatten();
+    #[cfg(feature = "parallel")]
+    let seeds = seed_ranges.into_par_iter().flatten();
+    seeds
+        .map(|seed| get_seed_location(seed, &maps))
+        .min()
+        .unwrap_or(-1)
 }
 
 fn get_seed_location(seed: i64, maps: &[Vec<(i64, i64, i64)>; 7]) -> i64 {
@@ -1,11 +1,53 @@
-use std::fs;
+use std::{env, fmt, fs};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Variant {
     NotKnown,
     Increasing,
     Decreasing,
 }
 
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Variant::NotKnown => write!(f, "no direction"),
+            Variant::Increasing => write!(f, "increasing"),
+            Variant::Decreasing => write!(f, "decreasing"),
+        }
+    }
+}
+
+/// Why a pair of adjacent levels makes a report unsafe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reason {
+    Equal,
+    TooLargeJump,
+    DirectionChange,
+}
+
+impl Reason {
+    const ALL: [Reason; 3] = [Reason::Equal, Reason::TooLargeJump, Reason::DirectionChange];
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Equal => write!(f, "equal"),
+            Reason::TooLargeJump => write!(f, "too large jump"),
+            Reason::DirectionChange => write!(f, "direction change"),
+        }
+    }
+}
+
+/// First pair of adjacent levels breaking the rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Violation {
+    /// Index of the first level of the pair
+    index: usize,
+    pair: (i32, i32),
+    reason: Reason,
+}
+
 fn main() {
     let path = "input.txt".to_string();
     let input = fs::read_to_string(path).expect("No input file found!");
@@ -20,10 +62,14 @@ fn main() {
         .collect();
 
     println!("Part 1: {}", part_1(&reports));
-    println!("Part 2: {}", part_2(reports));
+    println!("Part 2: {}", part_2(reports.clone()));
+
+    if env::args().any(|arg| arg == "--explain") {
+        print!("\n{}", explain(&reports));
+    }
 }
 
-fn part_1(reports: &Vec<Vec<i32>>) -> i32 {
+fn part_1(reports: &[Vec<i32>]) -> i32 {
     let mut total = 0;
 
     for report in reports {
@@ -50,56 +96,121 @@ fn part_2(reports: Vec<Vec<i32>>) -> i32 {
 }
 
 fn is_safe<'a, T: Iterator<Item = &'a i32>>(report: T) -> bool {
+    check(report).is_ok()
+}
+
+/// Direction of a safe report, or the first pair of levels that makes it unsafe.
+fn check<'a, T: Iterator<Item = &'a i32>>(report: T) -> Result<Variant, Violation> {
     use std::cmp::Ordering::*;
     use Variant::*;
 
     let mut previous: Option<i32> = None;
     let mut variant = NotKnown;
 
-    for number in report {
+    for (index, &number) in report.enumerate() {
         if let Some(value) = previous {
-            if matches!(variant, NotKnown) {
-                match number.cmp(&value) {
-                    Less => variant = Decreasing,
-                    Greater => variant = Increasing,
-                    Equal => {
-                        return false;
-                    }
-                }
+            let violation = |reason| Violation {
+                index: index - 1,
+                pair: (value, number),
+                reason,
+            };
+            let direction = match number.cmp(&value) {
+                Less => Decreasing,
+                Greater => Increasing,
+                Equal => return Err(violation(Reason::Equal)),
+            };
+            if variant == NotKnown {
+                variant = direction;
             }
 
-            match variant {
-                NotKnown => unreachable!("NotKnown handled separately."),
-                Increasing => {
-                    if *number <= value || number - value > 3 {
-                        return false;
-                    }
-                }
-                Decreasing => {
-                    if *number >= value || value - number > 3 {
-                        return false;
-                    }
-                }
+            if direction != variant {
+                return Err(violation(Reason::DirectionChange));
+            }
+            if (number - value).abs() > 3 {
+                return Err(violation(Reason::TooLargeJump));
             }
         }
-        previous = Some(*number);
+        previous = Some(number);
     }
-    true
+    Ok(variant)
 }
 
 fn is_safe_dampened(report: Vec<i32>) -> bool {
-    for i in 0..report.len() {
-        if is_safe(
+    dampened_removal(&report).is_some()
+}
+
+/// Index of the first level whose removal makes the report safe
+fn dampened_removal(report: &[i32]) -> Option<usize> {
+    (0..report.len()).find(|&i| {
+        is_safe(
             report
                 .iter()
                 .enumerate()
                 .filter(|(index, _)| *index != i)
                 .map(|(_, value)| value),
-        ) {
-            return true;
+        )
+    })
+}
+
+/// Why each report is safe or not, followed by a summary of the reasons.
+fn explain(reports: &[Vec<i32>]) -> String {
+    let mut lines = Vec::new();
+    let (mut safe, mut dampened) = (0, 0);
+    // Unsafe reports and how many of them the dampener fixes, per reason
+    let mut counts = [(0, 0); Reason::ALL.len()];
+
+    for report in reports {
+        let levels: Vec<String> = report.iter().map(i32::to_string).collect();
+        let levels = levels.join(" ");
+        match check(report.iter()) {
+            Ok(variant) => {
+                safe += 1;
+                lines.push(format!("{levels}: safe, {variant}"));
+            }
+            Err(violation) => {
+                let variant = check(report[..=violation.index].iter()).unwrap_or(Variant::NotKnown);
+                let (a, b) = violation.pair;
+                let removal = dampened_removal(report);
+                let reason = Reason::ALL
+                    .iter()
+                    .position(|&r| r == violation.reason)
+                    .unwrap();
+                counts[reason].0 += 1;
+                let outcome = match removal {
+                    Some(index) => {
+                        dampened += 1;
+                        counts[reason].1 += 1;
+                        format!("safe without index {index} ({})", report[index])
+                    }
+                    None => "unsafe with any level removed".to_string(),
+                };
+                lines.push(format!(
+                    "{levels}: unsafe, {variant}, first violation {a} {b} at index {} ({}); {outcome}",
+                    violation.index, violation.reason
+                ));
+            }
         }
     }
-    false
+
+    lines.push(String::new());
+    lines.push(format!(
+        "{:<18}{:>8}{:>20}",
+        "Reason", "Unsafe", "Safe when dampened"
+    ));
+    for (reason, (total, fixed)) in Reason::ALL.iter().zip(counts) {
+        lines.push(format!("{:<18}{total:>8}{fixed:>20}", reason.to_string()));
+    }
+    lines.push(format!(
+        "{:<18}{:>8}{dampened:>20}",
+        "Total",
+        reports.len() - safe
+    ));
+    lines.push(format!(
+        "{safe} of {} reports safe, {} with the dampener",
+        reports.len(),
+        safe + dampened
+    ));
+    lines.iter().map(|line| format!("{line}\n")).collect()
 }
 
 #[cfg(test)]
@@ -108,12 +219,65 @@ mod tests {
 
     #[test]
     fn test_is_safe() {
-        assert!(is_safe(vec![7, 6, 4, 2, 1].iter()));
-        assert!(!is_safe(vec![1, 2, 7, 8, 9].iter()));
-        assert!(!is_safe(vec![9, 7, 6, 2, 1].iter()));
-        assert!(!is_safe(vec![1, 3, 2, 4, 5].iter()));
-        assert!(!is_safe(vec![8, 6, 4, 4, 1].iter()));
-        assert!(is_safe(vec![1, 3, 6, 7, 9].iter()));
+        assert!(is_safe([7, 6, 4, 2, 1].iter()));
+        assert!(!is_safe([1, 2, 7, 8, 9].iter()));
+        assert!(!is_safe([9, 7, 6, 2, 1].iter()));
+        assert!(!is_safe([1, 3, 2, 4, 5].iter()));
+        assert!(!is_safe([8, 6, 4, 4, 1].iter()));
+        assert!(is_safe([1, 3, 6, 7, 9].iter()));
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(check([7, 6, 4, 2, 1].iter()), Ok(Variant::Decreasing));
+        assert_eq!(check([5].iter()), Ok(Variant::NotKnown));
+        assert_eq!(
+            check([1, 2, 7, 8, 9].iter()),
+            Err(Violation {
+                index: 1,
+                pair: (2, 7),
+                reason: Reason::TooLargeJump
+            })
+        );
+        assert_eq!(
+            check([1, 3, 2, 4, 5].iter()).map_err(|violation| violation.reason),
+            Err(Reason::DirectionChange)
+        );
+        assert_eq!(
+            check([8, 6, 4, 4, 1].iter()).map_err(|violation| violation.index),
+            Err(2)
+        );
+    }
+
+    #[test]
+    fn test_dampened_removal() {
+        assert_eq!(dampened_removal(&[1, 3, 2, 4, 5]), Some(1));
+        assert_eq!(dampened_removal(&[8, 6, 4, 4, 1]), Some(2));
+        assert_eq!(dampened_removal(&[9, 7, 6, 2, 1]), None);
+    }
+
+    #[test]
+    fn test_explain() {
+        let reports = vec![
+            vec![7, 6, 4, 2, 1],
+            vec![1, 2, 7, 8, 9],
+            vec![1, 3, 2, 4, 5],
+        ];
+        let explanation = explain(&reports);
+        let lines: Vec<&str> = explanation.lines().collect();
+        assert_eq!(lines[0], "7 6 4 2 1: safe, decreasing");
+        assert_eq!(
+            lines[1],
+            "1 2 7 8 9: unsafe, increasing, first violation 2 7 at index 1 (too large jump); unsafe with any level removed"
+        );
+        assert_eq!(
+            lines[2],
+            "1 3 2 4 5: unsafe, increasing, first violation 3 2 at index 1 (direction change); safe without index 1 (3)"
+        );
+        assert_eq!(
+            lines.last(),
+            Some(&"1 of 3 reports safe, 2 with the dampener")
+        );
     }
 
     #[test]
@@ -1,32 +1,169 @@
-use regex::Regex;
 use std::{fs, path::Path};
 
 fn main() {
     let path = Path::new("input.txt");
-    let input = fs::read_to_string(path)
-        .expect("Input file not found!")
-        .replace("\n", "");
+    let input = fs::read_to_string(path).expect("Input file not found!");
 
     println!("Part 1: {}", part_1(&input));
     println!("Part 2: {}", part_2(&input));
 }
 
 fn part_1(input: &str) -> u32 {
-    let mut result: u32 = 0;
-    let pattern = Regex::new(r"mul\(([0-9]{1,3}),([0-9]{1,3})\)").unwrap();
-    for (_, [a, b]) in pattern.captures_iter(&input).map(|c| c.extract()) {
-        result += a.parse::<u32>().unwrap() * b.parse::<u32>().unwrap();
-    }
-    result
+    tokenize(input, INSTRUCTIONS)
+        .map(|instruction| match instruction {
+            Instruction::Mul(a, b) => a * b,
+            _ => 0,
+        })
+        .sum()
 }
 
 fn part_2(input: &str) -> u32 {
-    let mut result: u32 = 0;
-    let pattern = Regex::new(r"(^.*?don't\(\)|do\(\).*?don't\(\)|do\(\).*$)").unwrap();
-    for do_block in pattern.find_iter(input).map(|c| c.as_str()) {
-        result += part_1(do_block);
+    let mut machine = Machine::default();
+    for instruction in tokenize(input, INSTRUCTIONS) {
+        machine.execute(instruction);
+    }
+    machine.result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    Mul(u32, u32),
+    Do,
+    Dont,
+}
+
+/// Describes how an instruction looks in the corrupted memory: `name(arg,...)`.
+///
+/// New instructions are added by adding a variant to `Instruction` and a spec
+/// to the instruction set passed to `tokenize`.
+struct InstructionSpec {
+    name: &'static str,
+    arity: usize,
+    build: fn(&[u32]) -> Instruction,
+}
+
+const INSTRUCTIONS: &[InstructionSpec] = &[
+    InstructionSpec {
+        name: "mul",
+        arity: 2,
+        build: |args| Instruction::Mul(args[0], args[1]),
+    },
+    InstructionSpec {
+        name: "do",
+        arity: 0,
+        build: |_| Instruction::Do,
+    },
+    InstructionSpec {
+        name: "don't",
+        arity: 0,
+        build: |_| Instruction::Dont,
+    },
+];
+
+/// Maximum number of digits allowed in a single argument.
+const MAX_DIGITS: usize = 3;
+
+/// Iterate over the valid instructions in `input`, skipping everything else.
+fn tokenize<'a>(
+    input: &'a str,
+    instructions: &'a [InstructionSpec],
+) -> impl Iterator<Item = Instruction> + 'a {
+    Tokenizer {
+        input: input.as_bytes(),
+        position: 0,
+        instructions,
+    }
+}
+
+struct Tokenizer<'a> {
+    input: &'a [u8],
+    position: usize,
+    instructions: &'a [InstructionSpec],
+}
+
+impl Iterator for Tokenizer<'_> {
+    type Item = Instruction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.position < self.input.len() {
+            let rest = &self.input[self.position..];
+            for spec in self.instructions {
+                if let Some((instruction, length)) = parse_instruction(rest, spec) {
+                    self.position += length;
+                    return Some(instruction);
+                }
+            }
+            self.position += 1;
+        }
+        None
+    }
+}
+
+/// Try to parse `spec` from the start of `input`.
+///
+/// Returns the instruction and the number of bytes consumed.
+fn parse_instruction(input: &[u8], spec: &InstructionSpec) -> Option<(Instruction, usize)> {
+    let mut position = spec.name.len();
+    if !input.starts_with(spec.name.as_bytes()) || input.get(position) != Some(&b'(') {
+        return None;
+    }
+    position += 1;
+
+    let mut args = Vec::with_capacity(spec.arity);
+    for i in 0..spec.arity {
+        if i > 0 {
+            if input.get(position) != Some(&b',') {
+                return None;
+            }
+            position += 1;
+        }
+        let digits = input[position..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if digits == 0 || digits > MAX_DIGITS {
+            return None;
+        }
+        let value = input[position..position + digits]
+            .iter()
+            .fold(0, |value, c| value * 10 + (c - b'0') as u32);
+        args.push(value);
+        position += digits;
+    }
+
+    if input.get(position) != Some(&b')') {
+        return None;
+    }
+    Some(((spec.build)(&args), position + 1))
+}
+
+/// Executes instructions, keeping track of whether `mul` is enabled.
+struct Machine {
+    enabled: bool,
+    result: u32,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            result: 0,
+        }
+    }
+}
+
+impl Machine {
+    fn execute(&mut self, instruction: Instruction) {
+        match instruction {
+            Instruction::Mul(a, b) => {
+                if self.enabled {
+                    self.result += a * b;
+                }
+            }
+            Instruction::Do => self.enabled = true,
+            Instruction::Dont => self.enabled = false,
+        }
     }
-    result
 }
 
 #[cfg(test)]
@@ -48,4 +185,13 @@ mod tests {
             48
         );
     }
+
+    #[test]
+    fn test_tokenize() {
+        use Instruction::*;
+        let instructions: Vec<_> =
+            tokenize("mul(1,2)do()mul(1234,5)mul(4,5 )don't()\nmul(mul(6,7)", INSTRUCTIONS)
+                .collect();
+        assert_eq!(instructions, vec![Mul(1, 2), Do, Dont, Mul(6, 7)]);
+    }
 }
@@ -0,0 +1,174 @@
+//! Word and pattern search over a grid of characters.
+
+/// All 8 directions as `(di, dj)`, starting from up and going clockwise.
+pub const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WordMatch {
+    /// Position `(i, j)` of the first character of the word
+    pub start: (usize, usize),
+    pub direction: (isize, isize),
+}
+
+/// Find all occurrences of `word` in any of the 8 directions.
+///
+/// With `wrap`, words may continue over the grid edges to the opposite side.
+pub fn find_word(grid: &[Vec<char>], word: &str, wrap: bool) -> Vec<WordMatch> {
+    let word: Vec<char> = word.chars().collect();
+    let mut matches = Vec::new();
+    let Some(&first) = word.first() else {
+        return matches;
+    };
+
+    for (i, row) in grid.iter().enumerate() {
+        for (j, &c) in row.iter().enumerate() {
+            if c != first {
+                continue;
+            }
+            for direction in DIRECTIONS {
+                let found = word.iter().enumerate().skip(1).all(|(k, &expected)| {
+                    step(grid, (i, j), direction, k, wrap)
+                        .is_some_and(|(i, j)| grid[i][j] == expected)
+                });
+                if found {
+                    matches.push(WordMatch {
+                        start: (i, j),
+                        direction,
+                    });
+                }
+            }
+        }
+    }
+    matches
+}
+
+/// Position `distance` steps away from `start`, or `None` if outside the grid.
+fn step(
+    grid: &[Vec<char>],
+    (i, j): (usize, usize),
+    (di, dj): (isize, isize),
+    distance: usize,
+    wrap: bool,
+) -> Option<(usize, usize)> {
+    let (height, width) = (grid.len() as isize, grid[0].len() as isize);
+    let i = i as isize + di * distance as isize;
+    let j = j as isize + dj * distance as isize;
+    if wrap {
+        Some((i.rem_euclid(height) as usize, j.rem_euclid(width) as usize))
+    } else if (0..height).contains(&i) && (0..width).contains(&j) {
+        Some((i as usize, j as usize))
+    } else {
+        None
+    }
+}
+
+/// A 2D mask of characters, where `None` matches anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    cells: Vec<Vec<Option<char>>>,
+}
+
+impl Pattern {
+    /// Parse a pattern from lines of text, `wildcard` marks cells that match anything.
+    pub fn new(pattern: &str, wildcard: char) -> Self {
+        let cells: Vec<Vec<Option<char>>> = pattern
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .map(|c| if c == wildcard { None } else { Some(c) })
+                    .collect()
+            })
+            .collect();
+        assert!(
+            cells.iter().all(|row| row.len() == cells[0].len()),
+            "Pattern rows must be of equal length!"
+        );
+        Self { cells }
+    }
+
+    fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn width(&self) -> usize {
+        self.cells.first().map_or(0, |row| row.len())
+    }
+
+    /// Rotate the pattern 90 degrees clockwise.
+    pub fn rotate(&self) -> Self {
+        let cells = (0..self.width())
+            .map(|j| (0..self.height()).rev().map(|i| self.cells[i][j]).collect())
+            .collect();
+        Self { cells }
+    }
+
+    /// Mirror the pattern horizontally.
+    pub fn reflect(&self) -> Self {
+        let cells = self
+            .cells
+            .iter()
+            .map(|row| row.iter().rev().copied().collect())
+            .collect();
+        Self { cells }
+    }
+
+    /// All distinct rotations and reflections of the pattern, including itself.
+    pub fn variants(&self) -> Vec<Self> {
+        let mut variants: Vec<Self> = Vec::new();
+        let mut current = self.clone();
+        for _ in 0..4 {
+            for variant in [current.clone(), current.reflect()] {
+                if !variants.contains(&variant) {
+                    variants.push(variant);
+                }
+            }
+            current = current.rotate();
+        }
+        variants
+    }
+
+    fn matches_at(&self, grid: &[Vec<char>], i: usize, j: usize) -> bool {
+        self.cells.iter().enumerate().all(|(di, row)| {
+            row.iter().enumerate().all(|(dj, cell)| match cell {
+                Some(c) => grid[i + di][j + dj] == *c,
+                None => true,
+            })
+        })
+    }
+}
+
+/// Find the top-left positions of all places where `pattern` matches.
+///
+/// With `transform`, all rotations and reflections of the pattern are searched
+/// too, and a position is reported once per matching variant.
+pub fn find_pattern(grid: &[Vec<char>], pattern: &Pattern, transform: bool) -> Vec<(usize, usize)> {
+    let variants = if transform {
+        pattern.variants()
+    } else {
+        vec![pattern.clone()]
+    };
+
+    let mut matches = Vec::new();
+    for variant in variants {
+        if grid.len() < variant.height() || grid[0].len() < variant.width() {
+            continue;
+        }
+        for i in 0..=(grid.len() - variant.height()) {
+            for j in 0..=(grid[0].len() - variant.width()) {
+                if variant.matches_at(grid, i, j) {
+                    matches.push((i, j));
+                }
+            }
+        }
+    }
+    matches
+}
@@ -1,9 +1,8 @@
-use std::{collections::HashMap, fs, path::Path};
+use std::{env, fs, path::Path};
 
-struct Page<'a> {
-    number: u32,
-    ordering_rules: &'a HashMap<u32, Vec<u32>>,
-}
+mod ordering;
+
+use ordering::{is_ordered, sort, violations, Rules};
 
 fn main() {
     let path = Path::new("input.txt");
@@ -12,35 +11,82 @@ fn main() {
 
     // `rules` contains all page numbers (`Vec<u32>`) that must come after the key
     let rules = parse_rules(input.next().expect("Input should contain rules."));
-    let mut updates = parse_updates(input.next().expect("Input should contain updates."), &rules);
+    let updates = parse_updates(input.next().expect("Input should contain updates."));
+
+    println!("Part 1: {}", part_1(&rules, &updates));
+    println!("Part 2: {}", part_2(&rules, &updates));
+
+    if env::args().any(|arg| arg == "--explain") {
+        print!("\n{}", explain(&rules, &updates));
+    }
+}
 
-    println!("Part 1: {}", part_1(&updates));
-    println!("Part 2: {}", part_2(&mut updates));
+fn part_1(rules: &Rules, updates: &[Vec<u32>]) -> u32 {
+    updates
+        .iter()
+        .filter(|update| is_ordered(rules, update))
+        .map(|update| update[update.len() / 2])
+        .sum()
 }
 
-fn part_1(updates: &Vec<Vec<Page>>) -> u32 {
+fn part_2(rules: &Rules, updates: &[Vec<u32>]) -> u32 {
     let mut sum = 0;
     for update in updates {
-        if is_ordered(&update) {
-            sum += update[update.len() / 2].number;
+        if !is_ordered(rules, update) {
+            let sorted = sort(rules, update).unwrap_or_else(|error| panic!("{update:?}: {error}"));
+            sum += sorted[sorted.len() / 2];
         }
     }
     sum
 }
 
-fn part_2(updates: &mut Vec<Vec<Page>>) -> u32 {
-    let mut sum = 0;
+/// For every incorrectly ordered update, the rules it breaks, the corrected
+/// order and its middle page.
+fn explain(rules: &Rules, updates: &[Vec<u32>]) -> String {
+    let join = |pages: &[u32]| {
+        pages
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let mut output = String::new();
+    let (mut incorrect, mut sum) = (0, 0);
+
     for update in updates {
-        if !is_ordered(&update) {
-            update.sort_by(|a, b| a.partial_cmp(b).unwrap());
-            sum += update[update.len() / 2].number;
+        let broken = violations(rules, update);
+        if broken.is_empty() {
+            continue;
+        }
+        incorrect += 1;
+        let broken: Vec<String> = broken.iter().map(|(a, b)| format!("{a}|{b}")).collect();
+        output.push_str(&format!(
+            "{}\n  violates {}\n",
+            join(update),
+            broken.join(" ")
+        ));
+        match sort(rules, update) {
+            Ok(sorted) => {
+                let middle = sorted[sorted.len() / 2];
+                sum += middle;
+                output.push_str(&format!(
+                    "  corrected {}, middle page {middle}\n",
+                    join(&sorted)
+                ));
+            }
+            Err(error) => output.push_str(&format!("  cannot be corrected: {error}\n")),
         }
     }
-    sum
+
+    output.push_str(&format!(
+        "{incorrect} of {} updates incorrectly ordered, middle page sum {sum}\n",
+        updates.len()
+    ));
+    output
 }
 
-fn parse_rules(input: &str) -> HashMap<u32, Vec<u32>> {
-    let mut rules = HashMap::new();
+fn parse_rules(input: &str) -> Rules {
+    let mut rules = Rules::new();
     for line in input.lines() {
         let mut split = line.split("|");
         let a = split.next().unwrap().parse().unwrap();
@@ -53,74 +99,21 @@ fn parse_rules(input: &str) -> HashMap<u32, Vec<u32>> {
     rules
 }
 
-fn parse_updates<'a>(input: &str, rules: &'a HashMap<u32, Vec<u32>>) -> Vec<Vec<Page<'a>>> {
+fn parse_updates(input: &str) -> Vec<Vec<u32>> {
     input
         .lines()
         .map(|line| {
             line.split(",")
-                .map(|page_number| Page {
-                    number: page_number
+                .map(|page_number| {
+                    page_number
                         .parse()
-                        .expect("Page numbers should be numeric values."),
-                    ordering_rules: rules,
+                        .expect("Page numbers should be numeric values.")
                 })
                 .collect()
         })
         .collect()
 }
 
-fn is_ordered(update: &Vec<Page>) -> bool {
-    let mut previous = &update[0];
-    for page in &update[1..] {
-        if page < previous {
-            return false;
-        }
-        previous = page;
-    }
-    true
-}
-
-impl PartialEq for Page<'_> {
-    fn eq(&self, other: &Self) -> bool {
-        self.number == other.number
-    }
-}
-
-impl PartialOrd for Page<'_> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        let self_order_vec = self.ordering_rules.get(&self.number);
-        let other_order_vec = self.ordering_rules.get(&other.number);
-        if self == other {
-            return Some(std::cmp::Ordering::Equal);
-        } else if self_order_vec.is_some() {
-            match self_order_vec.unwrap().contains(&other.number) {
-                true => return Some(std::cmp::Ordering::Less),
-                false => {
-                    if other_order_vec.is_some() && other_order_vec.unwrap().contains(&self.number)
-                    {
-                        return Some(std::cmp::Ordering::Greater);
-                    } else {
-                        return None;
-                    }
-                }
-            }
-        } else if other_order_vec.is_some() {
-            match other_order_vec.unwrap().contains(&self.number) {
-                true => return Some(std::cmp::Ordering::Greater),
-                false => {
-                    if self_order_vec.is_some() && self_order_vec.unwrap().contains(&other.number) {
-                        return Some(std::cmp::Ordering::Less);
-                    } else {
-                        return None;
-                    }
-                }
-            }
-        } else {
-            return None;
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -156,30 +149,89 @@ mod tests {
     #[test]
     fn test_is_ordered() {
         let rules = parse_rules(RULES);
-        let updates = parse_updates(UPDATES, &rules);
-        assert!(is_ordered(&updates[0]));
-        assert!(is_ordered(&updates[1]));
-        assert!(!is_ordered(&updates[3]));
-        assert!(!is_ordered(&updates[4]));
+        let updates = parse_updates(UPDATES);
+        assert!(is_ordered(&rules, &updates[0]));
+        assert!(is_ordered(&rules, &updates[1]));
+        assert!(!is_ordered(&rules, &updates[3]));
+        assert!(!is_ordered(&rules, &updates[4]));
     }
 
     #[test]
     fn test_parse_rules() {
         let rules = parse_rules("47|53\n97|13\n97|61");
-        assert_eq!(rules, HashMap::from([(47, vec![53]), (97, vec![13, 61]),]))
+        assert_eq!(rules, Rules::from([(47, vec![53]), (97, vec![13, 61]),]))
     }
 
     #[test]
     fn test_part_1() {
         let rules = parse_rules(RULES);
-        let updates = parse_updates(UPDATES, &rules);
-        assert_eq!(part_1(&updates), 143);
+        let updates = parse_updates(UPDATES);
+        assert_eq!(part_1(&rules, &updates), 143);
     }
 
     #[test]
     fn test_part_2() {
         let rules = parse_rules(RULES);
-        let mut updates = parse_updates(UPDATES, &rules);
-        assert_eq!(part_2(&mut updates), 123);
+        let updates = parse_updates(UPDATES);
+        assert_eq!(part_2(&rules, &updates), 123);
+    }
+
+    #[test]
+    fn test_explain() {
+        let rules = parse_rules(RULES);
+        let updates = parse_updates(UPDATES);
+        let explanation = explain(&rules, &updates);
+        let lines: Vec<&str> = explanation.lines().collect();
+        assert_eq!(lines.len(), 3 * 3 + 1);
+        assert_eq!(lines[0], "75,97,47,61,53");
+        assert_eq!(lines[1], "  violates 97|75");
+        assert_eq!(lines[2], "  corrected 97,75,47,61,53, middle page 47");
+        assert_eq!(lines[4], "  violates 29|13");
+        assert_eq!(
+            lines[9],
+            "3 of 6 updates incorrectly ordered, middle page sum 123"
+        );
+
+        let rules = parse_rules("1|2\n2|1");
+        let explanation = explain(&rules, &[vec![2, 1]]);
+        assert!(explanation.contains("  cannot be corrected: "));
+    }
+
+    #[test]
+    fn test_sort() {
+        let rules = parse_rules(RULES);
+        assert_eq!(
+            sort(&rules, &[75, 97, 47, 61, 53]),
+            Ok(vec![97, 75, 47, 61, 53])
+        );
+        assert_eq!(
+            sort(&rules, &[97, 13, 75, 29, 47]),
+            Ok(vec![97, 75, 47, 29, 13])
+        );
+        assert_eq!(ordering::violations(&rules, &[61, 13, 29]), vec![(29, 13)]);
+    }
+
+    #[test]
+    fn test_sort_errors() {
+        use ordering::OrderingError;
+        let rules = parse_rules("1|2\n2|3\n3|1\n3|4");
+        assert_eq!(
+            sort(&rules, &[4, 3, 2, 1]),
+            Err(OrderingError::Cycle(vec![(1, 2), (2, 3), (3, 1)]))
+        );
+        assert_eq!(sort(&rules, &[1, 4]), Err(OrderingError::Ambiguous(1, 4)));
+        assert_eq!(sort(&rules, &[4, 3]), Ok(vec![3, 4]));
+    }
+
+    #[test]
+    fn test_sort_repeated_page() {
+        let rules = parse_rules(RULES);
+        assert_eq!(sort(&rules, &[75, 97, 75, 47]), Ok(vec![97, 75, 47]));
+        assert_eq!(sort(&rules, &[61, 61]), Ok(vec![61]));
+        let rules = parse_rules("1|2\n2|3\n3|1");
+        assert_eq!(
+            sort(&rules, &[3, 1, 2, 1]),
+            Err(ordering::OrderingError::Cycle(vec![(1, 2), (2, 3), (3, 1)]))
+        );
     }
 }
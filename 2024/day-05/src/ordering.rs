@@ -0,0 +1,140 @@
+//! Ordering of pages according to the `a|b` rules, using Kahn's algorithm.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// `rules[a]` contains all pages that must come after `a`.
+pub type Rules = HashMap<u32, Vec<u32>>;
+
+/// A single `a|b` rule: `a` must be printed before `b`.
+pub type Rule = (u32, u32);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderingError {
+    /// The rules contain a cycle, given as the rules forming it.
+    Cycle(Vec<Rule>),
+    /// Both pages could come next, since no rule orders them.
+    Ambiguous(u32, u32),
+}
+
+impl std::fmt::Display for OrderingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderingError::Cycle(rules) => {
+                let rules: Vec<String> = rules.iter().map(|(a, b)| format!("{a}|{b}")).collect();
+                write!(f, "rules form a cycle: {}", rules.join(", "))
+            }
+            OrderingError::Ambiguous(a, b) => {
+                write!(f, "no rule decides the order of {a} and {b}")
+            }
+        }
+    }
+}
+
+/// All rules that apply between pages of `update`.
+pub fn applicable_rules(rules: &Rules, update: &[u32]) -> Vec<Rule> {
+    let pages: HashSet<u32> = update.iter().copied().collect();
+    let mut applicable = Vec::new();
+    for &a in update {
+        for &b in rules.get(&a).into_iter().flatten() {
+            if pages.contains(&b) {
+                applicable.push((a, b));
+            }
+        }
+    }
+    applicable
+}
+
+/// Rules that `update` breaks, i.e. `a|b` where `b` is printed before `a`.
+pub fn violations(rules: &Rules, update: &[u32]) -> Vec<Rule> {
+    let index: HashMap<u32, usize> = update.iter().enumerate().map(|(i, &p)| (p, i)).collect();
+    applicable_rules(rules, update)
+        .into_iter()
+        .filter(|(a, b)| index[a] > index[b])
+        .collect()
+}
+
+pub fn is_ordered(rules: &Rules, update: &[u32]) -> bool {
+    violations(rules, update).is_empty()
+}
+
+/// Sort the pages of `update` using only the rules between them.
+///
+/// Fails if the rules contain a cycle, or if they do not define a unique order.
+/// A page printed more than once is only sorted once, where it was first seen.
+pub fn sort(rules: &Rules, update: &[u32]) -> Result<Vec<u32>, OrderingError> {
+    let mut seen = HashSet::new();
+    let pages: Vec<u32> = update
+        .iter()
+        .copied()
+        .filter(|&page| seen.insert(page))
+        .collect();
+    let applicable = applicable_rules(rules, &pages);
+    let mut successors: HashMap<u32, Vec<u32>> = HashMap::new();
+    let mut in_degree: HashMap<u32, usize> = pages.iter().map(|&page| (page, 0)).collect();
+    for &(a, b) in &applicable {
+        successors.entry(a).or_default().push(b);
+        *in_degree.get_mut(&b).unwrap() += 1;
+    }
+
+    // Keep the original order among the initial candidates for determinism
+    let mut queue: VecDeque<u32> = pages
+        .iter()
+        .copied()
+        .filter(|page| in_degree[page] == 0)
+        .collect();
+    let mut sorted = Vec::with_capacity(pages.len());
+
+    while let Some(page) = queue.pop_front() {
+        if let Some(&other) = queue.front() {
+            return Err(OrderingError::Ambiguous(page, other));
+        }
+        sorted.push(page);
+        for &next in successors.get(&page).into_iter().flatten() {
+            let degree = in_degree.get_mut(&next).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                queue.push_back(next);
+            }
+        }
+    }
+
+    if sorted.len() < in_degree.len() {
+        let remaining: HashSet<u32> = in_degree
+            .into_iter()
+            .filter(|&(_, degree)| degree > 0)
+            .map(|(page, _)| page)
+            .collect();
+        return Err(OrderingError::Cycle(find_cycle(&applicable, &remaining)));
+    }
+    Ok(sorted)
+}
+
+/// Find one cycle among `pages`, all of which must have a predecessor in `pages`.
+fn find_cycle(rules: &[Rule], pages: &HashSet<u32>) -> Vec<Rule> {
+    let mut predecessor: HashMap<u32, u32> = HashMap::new();
+    for &(a, b) in rules {
+        if pages.contains(&a) && pages.contains(&b) {
+            predecessor.insert(b, a);
+        }
+    }
+
+    // Walking backwards must eventually revisit a page, which lies on a cycle
+    let mut page = *pages.iter().min().unwrap();
+    let mut seen = HashSet::new();
+    while seen.insert(page) {
+        page = predecessor[&page];
+    }
+
+    let start = page;
+    let mut cycle = Vec::new();
+    loop {
+        let previous = predecessor[&page];
+        cycle.push((previous, page));
+        page = previous;
+        if page == start {
+            break;
+        }
+    }
+    cycle.reverse();
+    cycle
+}
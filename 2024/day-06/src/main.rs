@@ -1,61 +1,257 @@
-use core::panic;
-use std::{fs, path::Path, usize};
+use std::{env, fs, path::Path};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "export")]
+use render::Exporter;
+use render::{Canvas, Cell, Colour, Debugger, Renderer, Simulation};
 
 fn main() {
     let path = Path::new("input.txt");
     let input = fs::read_to_string(path).expect("Cannot solve without input!");
+    let args: Vec<String> = env::args().collect();
+    threads::configure(&args);
 
     println!("Part 1: {}", part_1(Map::new(&input)));
     println!("Part 2: {}", part_2(Map::new(&input)));
+
+    if args.iter().any(|arg| arg == "--render") {
+        let canvas = patrol(Map::new(&input), |_, _| {});
+        Renderer::new()
+            .legend(GUARD, "start")
+            .legend(OBSTACLE, "obstacle")
+            .legend(Cell::new('+').fg(Colour::Cyan), "patrol")
+            .draw(
+                &canvas,
+                &format!("Visited {} cells", part_1(Map::new(&input))),
+            )
+            .expect("Cannot write to stdout!");
+    }
+
+    if args.iter().any(|arg| arg == "--debug") {
+        Debugger::new(Patrol::new(Map::new(&input)))
+            .run(&Renderer::new().legend(OBSTACLE, "obstacle"))
+            .expect("Cannot write to stdout!");
+    }
+
+    #[cfg(feature = "export")]
+    if let Some(mut exporter) = Exporter::from_args(&args) {
+        let canvas = patrol(Map::new(&input), |map, canvas| {
+            exporter
+                .frame(|| {
+                    let mut frame = canvas.clone();
+                    frame.set(map.guard_j, map.guard_i, guard(&map.guard_direction));
+                    frame
+                })
+                .expect("Cannot write frame!");
+        });
+        let frames = exporter
+            .finish_with(|| canvas)
+            .expect("Cannot write frames!");
+        println!("Exported {frames} frames");
+    }
+    #[cfg(not(feature = "export"))]
+    if args.iter().any(|arg| arg == "--export") {
+        eprintln!("Ignoring --export, built without the export feature");
+    }
 }
 
 fn part_1(mut map: Map) -> u32 {
-    let mut count = 0;
-    let mut visited: Vec<(usize, usize)> = Vec::new();
+    let mut visited = vec![false; map.height * map.width];
+    walk(&mut map, |map| {
+        visited[map.cell(map.guard_i, map.guard_j)] = true
+    });
+    visited.iter().filter(|&&visited| visited).count() as u32
+}
 
+/// Move the guard until she leaves the map, calling `on_step` before every move
+fn walk(map: &mut Map, mut on_step: impl FnMut(&Map)) {
     loop {
-        if !visited.contains(&(map.guard_i, map.guard_j)) {
-            visited.push((map.guard_i, map.guard_j));
-            count += 1;
-        }
+        on_step(map);
         if map.tick() {
-            return count;
+            return;
         }
     }
 }
 
-fn part_2(mut map: Map) -> u32 {
-    let mut visited: Vec<(usize, usize)> = Vec::new();
+const GUARD: Cell = Cell::new('^').fg(Colour::BrightYellow);
+const OBSTACLE: Cell = Cell::new('#').fg(Colour::Grey);
 
-    loop {
-        if !visited.contains(&(map.guard_i, map.guard_j)) {
-            visited.push((map.guard_i, map.guard_j));
+/// The guard facing `direction`
+fn guard(direction: &Direction) -> Cell {
+    let symbol = match direction {
+        Direction::Up => '^',
+        Direction::Down => 'v',
+        Direction::Right => '>',
+        Direction::Left => '<',
+    };
+    Cell::new(symbol).fg(Colour::BrightYellow)
+}
+
+/// Map with the guard's patrol drawn on it, `|` and `-` for the direction she
+/// walks through a cell and `+` where she walks both ways. `on_step` sees the
+/// patrol so far before every move.
+fn patrol(mut map: Map, mut on_step: impl FnMut(&Map, &Canvas)) -> Canvas {
+    let mut canvas = Canvas::new(map.width, map.height, Cell::EMPTY);
+    for (i, row) in map.obstacles.iter().enumerate() {
+        for (j, &obstacle) in row.iter().enumerate() {
+            if obstacle {
+                canvas.set(j, i, OBSTACLE);
+            }
         }
-        if map.tick() {
-            break;
+    }
+
+    let start = (map.guard_i, map.guard_j);
+    walk(&mut map, |map| {
+        let symbol = match map.guard_direction {
+            Direction::Up | Direction::Down => '|',
+            Direction::Right | Direction::Left => '-',
+        };
+        let symbol = match canvas.get(map.guard_j, map.guard_i) {
+            Some(cell) if cell.symbol != symbol && cell != Cell::EMPTY => '+',
+            _ => symbol,
+        };
+        on_step(map, &canvas);
+        canvas.set(map.guard_j, map.guard_i, Cell::new(symbol).fg(Colour::Cyan));
+    });
+    canvas.set(start.1, start.0, GUARD);
+    canvas
+}
+
+/// Guard walking step by step, remembering where she has been to step back.
+struct Patrol {
+    map: Map,
+    /// Guard states before each step taken
+    history: Vec<(usize, usize, Direction)>,
+}
+
+impl Patrol {
+    fn new(map: Map) -> Self {
+        Self {
+            map,
+            history: Vec::new(),
+        }
+    }
+}
+
+impl Simulation for Patrol {
+    fn forward(&mut self) -> bool {
+        let state = (
+            self.map.guard_i,
+            self.map.guard_j,
+            self.map.guard_direction.clone(),
+        );
+        if self.map.tick() {
+            return false;
+        }
+        self.history.push(state);
+        true
+    }
+
+    fn back(&mut self) -> bool {
+        let Some(state) = self.history.pop() else {
+            return false;
+        };
+        (self.map.guard_i, self.map.guard_j, self.map.guard_direction) = state;
+        true
+    }
+
+    fn canvas(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.map.width, self.map.height, Cell::EMPTY);
+        for (i, row) in self.map.obstacles.iter().enumerate() {
+            for (j, &obstacle) in row.iter().enumerate() {
+                if obstacle {
+                    canvas.set(j, i, OBSTACLE);
+                }
+            }
         }
+        for &(i, j, _) in &self.history {
+            canvas.set(j, i, Cell::new('X').fg(Colour::Cyan));
+        }
+        canvas.set(
+            self.map.guard_j,
+            self.map.guard_i,
+            guard(&self.map.guard_direction),
+        );
+        canvas
     }
 
-    let mut count = 0;
-    for (i, j) in visited {
-        map.obstacles[i][j] = true;
-        if is_loop(&mut map) {
-            count += 1;
+    fn status(&self) -> String {
+        format!(
+            "guard {} at ({}, {})",
+            guard(&self.map.guard_direction).symbol,
+            self.map.guard_i,
+            self.map.guard_j
+        )
+    }
+}
+
+fn part_2(map: Map) -> u32 {
+    let jumps = Jumps::new(&map);
+    let states = map.height * map.width * 4;
+    let candidates = candidates(map);
+
+    #[cfg(not(feature = "parallel"))]
+    let loops = {
+        let mut visited = BitSet::new(states);
+        candidates
+            .iter()
+            .filter(|candidate| jumps.is_loop(candidate, &mut visited))
+            .count()
+    };
+    #[cfg(feature = "parallel")]
+    let loops = candidates
+        .par_iter()
+        .map_init(
+            || BitSet::new(states),
+            |visited, candidate| jumps.is_loop(candidate, visited),
+        )
+        .filter(|&is_loop| is_loop)
+        .count();
+
+    loops as u32
+}
+
+/// Position for a new obstacle, and the guard state right before reaching it.
+struct Candidate {
+    guard: (usize, usize),
+    direction: Direction,
+    obstacle: (usize, usize),
+}
+
+/// Every cell on the guard's patrol is a possible place for an obstacle. The
+/// guard behaves as before until she first walks into it, so the simulation
+/// for each candidate can start from there.
+fn candidates(mut map: Map) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    let mut visited = vec![false; map.height * map.width];
+    visited[map.cell(map.guard_i, map.guard_j)] = true;
+
+    loop {
+        let (i, j, direction) = (map.guard_i, map.guard_j, map.guard_direction.clone());
+        if map.tick() {
+            return candidates;
+        }
+        let cell = map.cell(map.guard_i, map.guard_j);
+        if (map.guard_i, map.guard_j) != (i, j) && !visited[cell] {
+            visited[cell] = true;
+            candidates.push(Candidate {
+                guard: (i, j),
+                direction,
+                obstacle: (map.guard_i, map.guard_j),
+            });
         }
-        map.obstacles[i][j] = false;
     }
-    count
 }
 
 #[derive(Debug)]
 struct Map {
     obstacles: Vec<Vec<bool>>,
+    height: usize,
+    width: usize,
     guard_i: usize,
     guard_j: usize,
     guard_direction: Direction,
-    initial_guard_i: usize,
-    initial_guard_j: usize,
-    initial_guard_direction: Direction,
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -75,6 +271,15 @@ impl Direction {
             Direction::Left => Direction::Up,
         }
     }
+
+    fn index(&self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
 }
 
 impl Map {
@@ -107,7 +312,7 @@ impl Map {
                         }
                         'v' => {
                             (guard_i, guard_j) = (i, j);
-                            guard_direction = Left;
+                            guard_direction = Down;
                             false
                         }
                         _ => panic!("Invalid input format!"),
@@ -116,16 +321,20 @@ impl Map {
             )
         }
         Self {
+            height: obstacles.len(),
+            width: obstacles.first().map_or(0, |row: &Vec<bool>| row.len()),
             obstacles,
             guard_i,
             guard_j,
-            guard_direction: guard_direction.clone(),
-            initial_guard_i: guard_i,
-            initial_guard_j: guard_j,
-            initial_guard_direction: guard_direction,
+            guard_direction,
         }
     }
 
+    /// Index of (i, j) in flattened per-cell data
+    fn cell(&self, i: usize, j: usize) -> usize {
+        i * self.width + j
+    }
+
     fn tick(&mut self) -> bool {
         if let Some(next_position) =
             self.next(self.guard_i, self.guard_j, self.guard_direction.clone())
@@ -158,7 +367,7 @@ impl Map {
                 }
             }
             Down => {
-                if i < self.obstacles.len() - 1 {
+                if i < self.height - 1 {
                     if self.obstacles[i + 1][j] {
                         direction = direction.turn();
                         Some((i, j, direction))
@@ -170,7 +379,7 @@ impl Map {
                 }
             }
             Right => {
-                if j < self.obstacles.len() - 1 {
+                if j < self.width - 1 {
                     if self.obstacles[i][j + 1] {
                         direction = direction.turn();
                         Some((i, j, direction))
@@ -197,90 +406,137 @@ impl Map {
     }
 }
 
-fn is_loop(map: &mut Map) -> bool {
-    let mut visited: Vec<(usize, usize, Direction)> = Vec::new();
-    map.guard_i = map.initial_guard_i;
-    map.guard_j = map.initial_guard_j;
-    map.guard_direction = map.initial_guard_direction.clone();
+/// Fixed size set of indices, with cheap clearing of the inserted ones.
+struct BitSet {
+    words: Vec<u64>,
+    inserted: Vec<usize>,
+}
 
-    loop {
-        if visited.contains(&(map.guard_i, map.guard_j, map.guard_direction.clone())) {
-            return true;
-        } else {
-            visited.push((map.guard_i, map.guard_j, map.guard_direction.clone()));
+impl BitSet {
+    fn new(size: usize) -> Self {
+        Self {
+            words: vec![0; size.div_ceil(64)],
+            inserted: Vec::new(),
         }
-        if map.tick() {
+    }
+
+    /// Returns false if `index` was already in the set
+    fn insert(&mut self, index: usize) -> bool {
+        let (word, bit) = (index / 64, 1 << (index % 64));
+        if self.words[word] & bit != 0 {
             return false;
         }
+        self.words[word] |= bit;
+        self.inserted.push(index);
+        true
     }
-}
-
-fn part_2_incomplete_alternative(mut map: Map) -> u32 {
-    use Direction::*;
-    let mut visited: Vec<(usize, usize, Direction)> = Vec::new();
-    let mut found_positions: Vec<(usize, usize)> = Vec::new();
 
-    loop {
-        if !visited.contains(&(map.guard_i, map.guard_j, map.guard_direction.clone())) {
-            visited.push((map.guard_i, map.guard_j, map.guard_direction.clone()));
-        }
-        if map.tick() {
-            break;
+    fn clear(&mut self) {
+        for index in self.inserted.drain(..) {
+            self.words[index / 64] = 0;
         }
     }
+}
 
-    for (i, j, direction) in visited.clone() {
-        if visited
-            .iter()
-            .filter(|(x, y, _)| *x == i && *y == j)
-            .count()
-            == 1
-        {
-            continue;
-        }
-        if let Some((next_i, next_j, _)) = map.next(i, j, direction.clone()) {
-            if next_i == map.initial_guard_i && next_j == map.initial_guard_j {
-                continue;
-            }
-        }
-        let turn_direction = direction.turn();
-        match turn_direction {
-            Up => {
-                for row in i..=0 {
-                    if visited.contains(&(row, j, turn_direction.clone())) {
-                        found_positions.push((row, j));
-                        break;
-                    }
+/// Where the guard stops when walking from each cell in each direction, right
+/// before the next obstacle. `None` if she walks off the map.
+struct Jumps {
+    width: usize,
+    /// Indexed by `cell * 4 + direction.index()`
+    stops: Vec<Option<(usize, usize)>>,
+}
+
+impl Jumps {
+    fn new(map: &Map) -> Self {
+        let (height, width) = (map.height, map.width);
+        let mut stops = vec![None; height * width * 4];
+        let mut set = |i: usize, j: usize, direction: Direction, stop| {
+            stops[(i * width + j) * 4 + direction.index()] = stop;
+        };
+
+        for j in 0..width {
+            let mut stop = None;
+            for i in 0..height {
+                if map.obstacles[i][j] {
+                    stop = Some((i + 1, j));
+                } else {
+                    set(i, j, Direction::Up, stop);
                 }
             }
-            Down => {
-                for row in i..map.obstacles.len() {
-                    if visited.contains(&(row, j, turn_direction.clone())) {
-                        found_positions.push((row, j));
-                        break;
-                    }
+            let mut stop = None;
+            for i in (0..height).rev() {
+                if map.obstacles[i][j] {
+                    stop = i.checked_sub(1).map(|i| (i, j));
+                } else {
+                    set(i, j, Direction::Down, stop);
                 }
             }
-            Right => {
-                for col in j..map.obstacles[0].len() {
-                    if visited.contains(&(i, col, turn_direction.clone())) {
-                        found_positions.push((i, col));
-                        break;
-                    }
+        }
+        for i in 0..height {
+            let mut stop = None;
+            for j in 0..width {
+                if map.obstacles[i][j] {
+                    stop = Some((i, j + 1));
+                } else {
+                    set(i, j, Direction::Left, stop);
                 }
             }
-            Left => {
-                for col in j..=0 {
-                    if visited.contains(&(i, col, turn_direction.clone())) {
-                        found_positions.push((i, col));
-                        break;
-                    }
+            let mut stop = None;
+            for j in (0..width).rev() {
+                if map.obstacles[i][j] {
+                    stop = j.checked_sub(1).map(|j| (i, j));
+                } else {
+                    set(i, j, Direction::Right, stop);
                 }
             }
         }
+        Self { width, stops }
+    }
+
+    /// Where the guard stops walking from (i, j), with an extra obstacle
+    fn stop(
+        &self,
+        (i, j): (usize, usize),
+        direction: &Direction,
+        obstacle: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let stop = self.stops[(i * self.width + j) * 4 + direction.index()];
+        let (o_i, o_j) = obstacle;
+        // The extra obstacle matters only if it is in the way before the stop
+        let blocking = match direction {
+            Direction::Up => o_j == j && o_i < i && stop.is_none_or(|(s_i, _)| o_i >= s_i),
+            Direction::Down => o_j == j && o_i > i && stop.is_none_or(|(s_i, _)| o_i <= s_i),
+            Direction::Left => o_i == i && o_j < j && stop.is_none_or(|(_, s_j)| o_j >= s_j),
+            Direction::Right => o_i == i && o_j > j && stop.is_none_or(|(_, s_j)| o_j <= s_j),
+        };
+        if !blocking {
+            return stop;
+        }
+        Some(match direction {
+            Direction::Up => (o_i + 1, o_j),
+            Direction::Down => (o_i - 1, o_j),
+            Direction::Left => (o_i, o_j + 1),
+            Direction::Right => (o_i, o_j - 1),
+        })
+    }
+
+    /// Whether the guard gets stuck in a loop after the candidate obstacle is added
+    fn is_loop(&self, candidate: &Candidate, visited: &mut BitSet) -> bool {
+        visited.clear();
+        let mut position = candidate.guard;
+        let mut direction = candidate.direction.clone();
+        loop {
+            let state = (position.0 * self.width + position.1) * 4 + direction.index();
+            if !visited.insert(state) {
+                return true;
+            }
+            match self.stop(position, &direction, candidate.obstacle) {
+                Some(stop) => position = stop,
+                None => return false,
+            }
+            direction = direction.turn();
+        }
     }
-    found_positions.dedup();
-    found_positions.len() as u32
 }
 
 #[cfg(test)]
@@ -308,6 +564,41 @@ mod tests {
         assert_eq!(part_2(Map::new(INPUT)), 6);
     }
 
+    #[test]
+    fn test_patrol() {
+        let rendered = Renderer::new()
+            .plain()
+            .render(&patrol(Map::new(INPUT), |_, _| {}), "");
+        assert_eq!(
+            rendered,
+            "....#.....
+....+---+#
+....|...|.
+..#.|...|.
+..+-+-+#|.
+..|.|.|.|.
+.#+-^-+-+.
+.+----++#.
+#+----+|..
+......#|..
+"
+        );
+    }
+
+    #[test]
+    fn test_debugger() {
+        let mut debugger = Debugger::new(Patrol::new(Map::new(INPUT)));
+        debugger.command("6");
+        assert_eq!(debugger.simulation.status(), "guard > at (1, 4)");
+        debugger.command("b");
+        assert_eq!(debugger.simulation.status(), "guard ^ at (1, 4)");
+        debugger.command("1000");
+        // 44 moves and 10 turns in place until the guard leaves
+        assert_eq!(debugger.step, 54);
+        debugger.command("g 0");
+        assert_eq!(debugger.simulation.status(), "guard ^ at (6, 4)");
+    }
+
     #[test]
     fn test_map() {
         let map = Map::new(INPUT);
@@ -316,23 +607,50 @@ mod tests {
         assert!(matches!(map.guard_direction, Direction::Up));
     }
 
+    #[test]
+    fn test_guard_facing_down() {
+        let map = Map::new("...\n.v.\n...");
+        assert!(matches!(map.guard_direction, Direction::Down));
+        assert_eq!(part_1(map), 2);
+    }
+
+    #[test]
+    fn test_non_square() {
+        let input = "..#.....\n.......#\n..^.....\n......#.";
+        assert_eq!(part_1(Map::new(input)), 12);
+        assert_eq!(part_2(Map::new(input)), 1);
+    }
+
     #[test]
     fn test_is_loop() {
-        let mut map = Map::new(INPUT);
-        map.obstacles[6][3] = true;
-        assert!(is_loop(&mut map));
-        map.obstacles[6][3] = false;
-        map.obstacles[4][4] = true;
-        assert!(!is_loop(&mut map));
-        map.obstacles[4][4] = false;
-        map.obstacles[8][1] = true;
-        assert!(is_loop(&mut map));
-        map.obstacles[8][1] = false;
-        map.obstacles[8][3] = true;
-        assert!(is_loop(&mut map));
-        map.obstacles[8][3] = false;
-        map.obstacles[9][7] = true;
-        assert!(is_loop(&mut map));
-        map.obstacles[9][7] = false;
+        let map = Map::new(INPUT);
+        let jumps = Jumps::new(&map);
+        let mut visited = BitSet::new(map.height * map.width * 4);
+        let is_loop = |obstacle, visited: &mut BitSet| {
+            let candidate = Candidate {
+                guard: (map.guard_i, map.guard_j),
+                direction: map.guard_direction.clone(),
+                obstacle,
+            };
+            jumps.is_loop(&candidate, visited)
+        };
+        assert!(is_loop((6, 3), &mut visited));
+        assert!(!is_loop((4, 4), &mut visited));
+        assert!(is_loop((8, 1), &mut visited));
+        assert!(is_loop((8, 3), &mut visited));
+        assert!(is_loop((9, 7), &mut visited));
+    }
+
+    #[test]
+    fn test_jumps() {
+        let map = Map::new(INPUT);
+        let jumps = Jumps::new(&map);
+        let outside = (usize::MAX, usize::MAX);
+        assert_eq!(jumps.stop((6, 4), &Direction::Up, outside), Some((1, 4)));
+        assert_eq!(jumps.stop((6, 4), &Direction::Up, (3, 4)), Some((4, 4)));
+        assert_eq!(jumps.stop((6, 4), &Direction::Down, outside), None);
+        assert_eq!(jumps.stop((6, 4), &Direction::Down, (8, 4)), Some((7, 4)));
+        assert_eq!(jumps.stop((6, 4), &Direction::Left, outside), Some((6, 2)));
+        assert_eq!(jumps.stop((1, 4), &Direction::Right, outside), Some((1, 8)));
     }
 }
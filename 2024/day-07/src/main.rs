@@ -1,72 +1,75 @@
-use std::{fs, path::Path};
+use std::{env, fs, path::Path};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+mod report;
+mod solver;
 
 fn main() {
     let path = Path::new("input.txt");
     let input = fs::read_to_string(path).expect("Cannot solve without input!");
+    let args: Vec<String> = env::args().collect();
+    threads::configure(&args);
 
     let parsed_input: Vec<(u64, Vec<u64>)> = input.lines().map(parse_line).collect();
 
+    // `--solutions one|all|count` reports the operators of each equation
+    // instead, with the operators of `--part 1|2` (default 2)
+    let value = |flag: &str| {
+        let index = args.iter().position(|arg| arg == flag)?;
+        Some(
+            args.get(index + 1)
+                .expect("Flag should have a value")
+                .as_str(),
+        )
+    };
+    if let Some(mode) = value("--solutions") {
+        let options = report::Options {
+            mode: mode.parse().unwrap(),
+            unsolvable: args.iter().any(|arg| arg == "--unsolvable"),
+            json: args.iter().any(|arg| arg == "--json"),
+        };
+        let operators = match value("--part") {
+            Some("1") => solver::PART_1,
+            Some("2") | None => solver::PART_2,
+            Some(part) => panic!("Unknown part {part}"),
+        };
+        print!("{}", report::report(&parsed_input, operators, options));
+        return;
+    }
+
     println!("Part 1: {}", part_1(&parsed_input));
     println!("Part 2: {}", part_2(&parsed_input));
 }
 
 fn part_1(input: &[(u64, Vec<u64>)]) -> u64 {
-    let mut sum = 0;
-    for equation in input {
-        if is_valid1(equation) {
-            sum += equation.0;
-        }
-    }
-    sum
+    calibration_result(input, is_valid1)
 }
 
 fn part_2(input: &[(u64, Vec<u64>)]) -> u64 {
-    let mut sum = 0;
-    for row in input {
-        if is_valid2(row) {
-            sum += row.0;
-        }
-    }
-    sum
+    calibration_result(input, is_valid2)
 }
 
-fn is_valid1(equation: &(u64, Vec<u64>)) -> bool {
-    let mut values = vec![equation.1[0]];
-    for number in equation.1.iter().skip(1) {
-        values = check1(*number, values);
-    }
-    values.contains(&equation.0)
+/// Sum of the targets of valid equations. Each equation is independent, so
+/// they are checked in parallel with the `parallel` feature.
+fn calibration_result(input: &[(u64, Vec<u64>)], is_valid: fn(&(u64, Vec<u64>)) -> bool) -> u64 {
+    #[cfg(not(feature = "parallel"))]
+    let equations = input.iter();
+    #[cfg(feature = "parallel")]
+    let equations = input.par_iter();
+    equations
+        .filter(|equation| is_valid(equation))
+        .map(|equation| equation.0)
+        .sum()
 }
 
-fn check1(number: u64, previous: Vec<u64>) -> Vec<u64> {
-    let mut values = Vec::new();
-    for value in previous {
-        values.push(number + value);
-        values.push(number * value);
-    }
-    values
+fn is_valid1(equation: &(u64, Vec<u64>)) -> bool {
+    solver::solve(equation.0, &equation.1, solver::PART_1).is_some()
 }
 
 fn is_valid2(equation: &(u64, Vec<u64>)) -> bool {
-    let mut values = vec![equation.1[0]];
-    for number in equation.1.iter().skip(1) {
-        values = check2(*number, values);
-    }
-    values.contains(&equation.0)
-}
-
-fn check2(number: u64, previous: Vec<u64>) -> Vec<u64> {
-    let mut values = Vec::new();
-    for value in previous {
-        values.push(number + value);
-        values.push(number * value);
-        values.push(concatenation(value, number));
-    }
-    values
-}
-
-fn concatenation(left: u64, right: u64) -> u64 {
-    format!("{left}{right}").parse().unwrap()
+    solver::solve(equation.0, &equation.1, solver::PART_2).is_some()
 }
 
 fn parse_line(line: &str) -> (u64, Vec<u64>) {
@@ -102,7 +105,117 @@ mod tests {
 
     #[test]
     fn test_concat() {
-        assert_eq!(concatenation(15, 6), 156);
+        use solver::{Concat, Operator};
+        assert_eq!(Concat.apply(15, 6), Some(156));
+        assert_eq!(Concat.apply(15, 0), Some(150));
+        assert_eq!(Concat.apply(u64::MAX, 1), None);
+        assert_eq!(Concat.undo(156, 6), Some(15));
+        assert_eq!(Concat.undo(156, 56), Some(1));
+        assert_eq!(Concat.undo(156, 7), None);
+    }
+
+    #[test]
+    fn test_solve() {
+        let operators = solver::solve(3267, &[81, 40, 27], solver::PART_1).unwrap();
+        assert_eq!(operators.len(), 2);
+        assert_eq!(solver::evaluate(&[81, 40, 27], &operators), Some(3267));
+
+        let operators = solver::solve(7290, &[6, 8, 6, 15], solver::PART_2).unwrap();
+        assert_eq!(solver::evaluate(&[6, 8, 6, 15], &operators), Some(7290));
+        assert!(solver::solve(83, &[17, 5], solver::PART_2).is_none());
+    }
+
+    #[test]
+    fn test_solve_multiply_by_zero() {
+        let operators = solver::solve(0, &[5, 0], solver::PART_1).unwrap();
+        assert_eq!(solver::expression(&[5, 0], &operators), "5 * 0");
+        let operators = solver::solve(0, &[7, 3, 0], solver::PART_1).unwrap();
+        assert_eq!(solver::evaluate(&[7, 3, 0], &operators), Some(0));
+        let operators = solver::solve(6, &[3, 0, 2, 3], solver::PART_1).unwrap();
+        assert_eq!(solver::evaluate(&[3, 0, 2, 3], &operators), Some(6));
+        assert!(is_valid1(&(0, vec![5, 0])));
+        assert!(is_valid2(&(0, vec![u64::MAX, 1, 0])));
+    }
+
+    #[test]
+    fn test_solve_all() {
+        let solutions = solver::solve_all(3267, &[81, 40, 27], solver::PART_1);
+        let expressions: Vec<String> = solutions
+            .iter()
+            .map(|operators| solver::expression(&[81, 40, 27], operators))
+            .collect();
+        assert_eq!(expressions, vec!["81 * 40 + 27", "81 + 40 * 27"]);
+        assert_eq!(solver::count(3267, &[81, 40, 27], solver::PART_1), 2);
+        assert_eq!(solver::count(156, &[15, 6], solver::PART_2), 1);
+        assert_eq!(solver::count(83, &[17, 5], solver::PART_2), 0);
+        assert!(solver::solve_all(83, &[17, 5], solver::PART_2).is_empty());
+    }
+
+    #[test]
+    fn test_solve_all_multiply_by_zero() {
+        let solutions = solver::solve_all(0, &[7, 3, 0], solver::PART_1);
+        let expressions: Vec<String> = solutions
+            .iter()
+            .map(|operators| solver::expression(&[7, 3, 0], operators))
+            .collect();
+        assert_eq!(expressions, vec!["7 + 3 * 0", "7 * 3 * 0"]);
+        assert_eq!(solver::count(0, &[7, 3, 0], solver::PART_1), 2);
+        assert_eq!(solver::count(0, &[7, 3, 0], solver::PART_2), 3);
+        // Only the prefixes that do not overflow count
+        assert_eq!(solver::count(0, &[u64::MAX, 1, 0], solver::PART_2), 1);
+    }
+
+    #[test]
+    fn test_report() {
+        use report::{Mode, Options};
+        let parsed_input: Vec<(u64, Vec<u64>)> = INPUT.lines().map(parse_line).collect();
+        let options = Options {
+            mode: Mode::One,
+            unsolvable: false,
+            json: false,
+        };
+        let output = report::report(&parsed_input, solver::PART_2, options);
+        assert_eq!(output.lines().count(), 6);
+        assert!(output.contains("156: 15 || 6\n"));
+
+        let options = Options {
+            mode: Mode::Count,
+            unsolvable: true,
+            ..options
+        };
+        let output = report::report(&parsed_input[1..3], solver::PART_1, options);
+        assert_eq!(output, "3267: 2 solutions\n83: no solution for 17 5\n");
+
+        let options = Options {
+            mode: Mode::All,
+            json: true,
+            ..options
+        };
+        let output = report::report(&parsed_input[1..3], solver::PART_1, options);
+        assert_eq!(
+            output,
+            "[\n  {\"target\": 3267, \"numbers\": [81, 40, 27], \"solutions\": [\"81 * 40 + 27\", \"81 + 40 * 27\"]},\n  {\"target\": 83, \"numbers\": [17, 5], \"solutions\": []}\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_report_multiply_by_zero() {
+        use report::{Mode, Options};
+        let equations = vec![(0, vec![5, 0]), (0, vec![7, 3, 0])];
+        let options = Options {
+            mode: Mode::All,
+            unsolvable: true,
+            json: false,
+        };
+        let output = report::report(&equations, solver::PART_1, options);
+        assert_eq!(output, "0: 5 * 0\n0: 7 + 3 * 0\n0: 7 * 3 * 0\n");
+
+        let options = Options {
+            mode: Mode::Count,
+            ..options
+        };
+        let output = report::report(&equations, solver::PART_2, options);
+        assert_eq!(output, "0: 1 solution\n0: 3 solutions\n");
     }
 
     #[test]
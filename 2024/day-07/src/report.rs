@@ -0,0 +1,148 @@
+//! Per-equation report of the operators that satisfy it.
+
+use std::str::FromStr;
+
+use crate::solver::{self, Operator};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// The first solution found
+    One,
+    /// Every solution
+    All,
+    /// Only the number of solutions
+    Count,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "one" => Ok(Mode::One),
+            "all" => Ok(Mode::All),
+            "count" => Ok(Mode::Count),
+            _ => Err(format!("Unknown mode {s}, expected one, all or count")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    pub mode: Mode,
+    /// Include equations without solutions
+    pub unsolvable: bool,
+    pub json: bool,
+}
+
+/// Solutions of one equation, as expressions
+struct Entry<'a> {
+    target: u64,
+    numbers: &'a [u64],
+    solutions: Vec<String>,
+    count: u64,
+}
+
+/// Solutions of every equation, as text lines or a JSON array.
+pub fn report(
+    equations: &[(u64, Vec<u64>)],
+    operators: &[&dyn Operator],
+    options: Options,
+) -> String {
+    let entries = equations.iter().map(|(target, numbers)| {
+        let expressions = |sequences: Vec<Vec<&dyn Operator>>| -> Vec<String> {
+            sequences
+                .iter()
+                .map(|sequence| solver::expression(numbers, sequence))
+                .collect()
+        };
+        let (solutions, count) = match options.mode {
+            Mode::One => {
+                let solutions = expressions(
+                    solver::solve(*target, numbers, operators)
+                        .into_iter()
+                        .collect(),
+                );
+                let count = solutions.len() as u64;
+                (solutions, count)
+            }
+            Mode::All => {
+                let solutions = expressions(solver::solve_all(*target, numbers, operators));
+                let count = solutions.len() as u64;
+                (solutions, count)
+            }
+            Mode::Count => (Vec::new(), solver::count(*target, numbers, operators)),
+        };
+        Entry {
+            target: *target,
+            numbers,
+            solutions,
+            count,
+        }
+    });
+    let entries: Vec<Entry> = entries
+        .filter(|entry| options.unsolvable || entry.count > 0)
+        .collect();
+
+    if options.json {
+        json(&entries, options.mode)
+    } else {
+        text(&entries, options.mode)
+    }
+}
+
+fn text(entries: &[Entry], mode: Mode) -> String {
+    let mut output = String::new();
+    for entry in entries {
+        if entry.count == 0 {
+            let numbers: Vec<String> = entry.numbers.iter().map(u64::to_string).collect();
+            output.push_str(&format!(
+                "{}: no solution for {}\n",
+                entry.target,
+                numbers.join(" ")
+            ));
+        } else if mode == Mode::Count {
+            let plural = if entry.count == 1 { "" } else { "s" };
+            output.push_str(&format!(
+                "{}: {} solution{plural}\n",
+                entry.target, entry.count
+            ));
+        } else {
+            for solution in &entry.solutions {
+                output.push_str(&format!("{}: {solution}\n", entry.target));
+            }
+        }
+    }
+    output
+}
+
+/// Built by hand, as the values are only numbers and operator symbols
+fn json(entries: &[Entry], mode: Mode) -> String {
+    let objects: Vec<String> = entries
+        .iter()
+        .map(|entry| {
+            let numbers: Vec<String> = entry.numbers.iter().map(u64::to_string).collect();
+            let result = match mode {
+                Mode::Count => format!("\"count\": {}", entry.count),
+                Mode::One | Mode::All => {
+                    let solutions: Vec<String> = entry
+                        .solutions
+                        .iter()
+                        .map(|solution| format!("\"{solution}\""))
+                        .collect();
+                    format!("\"solutions\": [{}]", solutions.join(", "))
+                }
+            };
+            format!(
+                "  {{\"target\": {}, \"numbers\": [{}], {result}}}",
+                entry.target,
+                numbers.join(", ")
+            )
+        })
+        .collect();
+    if objects.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", objects.join(",\n"))
+    }
+}
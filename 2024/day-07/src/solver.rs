@@ -0,0 +1,251 @@
+//! Solves calibration equations by working backwards from the target value.
+
+use std::ops::ControlFlow;
+
+pub trait Operator {
+    /// Evaluate `left <op> right`, `None` on overflow.
+    fn apply(&self, left: u64, right: u64) -> Option<u64>;
+
+    /// Find `left` such that `left <op> right == result`, if there is one.
+    fn undo(&self, result: u64, right: u64) -> Option<u64>;
+
+    /// Whether every `left` gives `left <op> right == result`, so `undo` has no
+    /// single answer.
+    fn absorbs(&self, _result: u64, _right: u64) -> bool {
+        false
+    }
+
+    /// How the operator is written in an expression.
+    fn symbol(&self) -> &'static str;
+}
+
+pub struct Add;
+pub struct Mul;
+pub struct Concat;
+
+impl Operator for Add {
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_add(right)
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Option<u64> {
+        result.checked_sub(right)
+    }
+
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+}
+
+impl Operator for Mul {
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_mul(right)
+    }
+
+    /// Multiplying by zero is covered by `absorbs` instead.
+    fn undo(&self, result: u64, right: u64) -> Option<u64> {
+        if right != 0 && result.is_multiple_of(right) {
+            Some(result / right)
+        } else {
+            None
+        }
+    }
+
+    fn absorbs(&self, result: u64, right: u64) -> bool {
+        result == 0 && right == 0
+    }
+
+    fn symbol(&self) -> &'static str {
+        "*"
+    }
+}
+
+impl Operator for Concat {
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_mul(digit_shift(right)?)?.checked_add(right)
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Option<u64> {
+        let shift = digit_shift(right)?;
+        if result % shift == right {
+            Some(result / shift)
+        } else {
+            None
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        "||"
+    }
+}
+
+/// Smallest power of ten greater than `number`, i.e. how much the left side of
+/// a concatenation gets shifted.
+fn digit_shift(number: u64) -> Option<u64> {
+    10_u64.checked_pow(number.checked_ilog10().unwrap_or(0) + 1)
+}
+
+pub const PART_1: &[&dyn Operator] = &[&Add, &Mul];
+pub const PART_2: &[&dyn Operator] = &[&Add, &Mul, &Concat];
+
+/// Find operators that make `numbers`, evaluated left to right, equal `target`.
+///
+/// Returns one satisfying sequence of operators, one between each pair of numbers.
+pub fn solve<'a>(
+    target: u64,
+    numbers: &[u64],
+    operators: &[&'a dyn Operator],
+) -> Option<Vec<&'a dyn Operator>> {
+    let sequence = solve_backwards(target, numbers, operators)?;
+    debug_assert_eq!(evaluate(numbers, &sequence), Some(target));
+    Some(sequence)
+}
+
+fn solve_backwards<'a>(
+    target: u64,
+    numbers: &[u64],
+    operators: &[&'a dyn Operator],
+) -> Option<Vec<&'a dyn Operator>> {
+    let (&last, rest) = numbers.split_last()?;
+    if rest.is_empty() {
+        return (target == last).then(Vec::new);
+    }
+    for &operator in operators {
+        let sequence = if operator.absorbs(target, last) {
+            let mut first = None;
+            evaluable(rest, operators, &mut |sequence| {
+                first = Some(sequence.to_vec());
+                ControlFlow::Break(())
+            });
+            first
+        } else {
+            operator
+                .undo(target, last)
+                .and_then(|previous| solve_backwards(previous, rest, operators))
+        };
+        if let Some(mut sequence) = sequence {
+            sequence.push(operator);
+            return Some(sequence);
+        }
+    }
+    None
+}
+
+/// Every sequence of operators that makes `numbers` equal `target`.
+pub fn solve_all<'a>(
+    target: u64,
+    numbers: &[u64],
+    operators: &[&'a dyn Operator],
+) -> Vec<Vec<&'a dyn Operator>> {
+    let Some((&last, rest)) = numbers.split_last() else {
+        return Vec::new();
+    };
+    if rest.is_empty() {
+        return if target == last {
+            vec![Vec::new()]
+        } else {
+            Vec::new()
+        };
+    }
+    let mut solutions = Vec::new();
+    for &operator in operators {
+        let sequences = if operator.absorbs(target, last) {
+            let mut sequences = Vec::new();
+            evaluable(rest, operators, &mut |sequence| {
+                sequences.push(sequence.to_vec());
+                ControlFlow::Continue(())
+            });
+            sequences
+        } else if let Some(previous) = operator.undo(target, last) {
+            solve_all(previous, rest, operators)
+        } else {
+            Vec::new()
+        };
+        for mut sequence in sequences {
+            sequence.push(operator);
+            solutions.push(sequence);
+        }
+    }
+    solutions
+}
+
+/// Number of operator sequences that make `numbers` equal `target`, without
+/// building them.
+pub fn count(target: u64, numbers: &[u64], operators: &[&dyn Operator]) -> u64 {
+    let Some((&last, rest)) = numbers.split_last() else {
+        return 0;
+    };
+    if rest.is_empty() {
+        return (target == last) as u64;
+    }
+    operators
+        .iter()
+        .map(|operator| {
+            if operator.absorbs(target, last) {
+                let mut count = 0;
+                evaluable(rest, operators, &mut |_| {
+                    count += 1;
+                    ControlFlow::Continue(())
+                });
+                count
+            } else {
+                operator
+                    .undo(target, last)
+                    .map_or(0, |previous| count(previous, rest, operators))
+            }
+        })
+        .sum()
+}
+
+/// Visit every sequence of operators that evaluates `numbers` without
+/// overflow, whatever the result, until `visit` breaks. Any of them solves an
+/// equation whose last operator absorbs the rest, like `... * 0 == 0`.
+fn evaluable<'a>(
+    numbers: &[u64],
+    operators: &[&'a dyn Operator],
+    visit: &mut impl FnMut(&[&'a dyn Operator]) -> ControlFlow<()>,
+) {
+    fn walk<'a>(
+        value: u64,
+        numbers: &[u64],
+        operators: &[&'a dyn Operator],
+        sequence: &mut Vec<&'a dyn Operator>,
+        visit: &mut impl FnMut(&[&'a dyn Operator]) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let Some((&next, rest)) = numbers.split_first() else {
+            return visit(sequence);
+        };
+        for &operator in operators {
+            if let Some(value) = operator.apply(value, next) {
+                sequence.push(operator);
+                let flow = walk(value, rest, operators, sequence, visit);
+                sequence.pop();
+                flow?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    if let Some((&first, rest)) = numbers.split_first() {
+        let _ = walk(first, rest, operators, &mut Vec::new(), visit);
+    }
+}
+
+/// `numbers` joined by `operators`, e.g. `81 + 40 * 27`.
+pub fn expression(numbers: &[u64], operators: &[&dyn Operator]) -> String {
+    let mut expression = numbers.first().map_or(String::new(), u64::to_string);
+    for (number, operator) in numbers.iter().skip(1).zip(operators) {
+        expression.push_str(&format!(" {} {number}", operator.symbol()));
+    }
+    expression
+}
+
+/// Evaluate `numbers` left to right with the given operators, `None` on overflow.
+pub fn evaluate(numbers: &[u64], operators: &[&dyn Operator]) -> Option<u64> {
+    let (&first, rest) = numbers.split_first()?;
+    rest.iter()
+        .zip(operators)
+        .try_fold(first, |value, (&number, operator)| {
+            operator.apply(value, number)
+        })
+}
@@ -1,116 +1,95 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs,
-    path::Path,
-    time::Instant,
-};
+use std::{collections::HashMap, env, fs, path::Path, time::Instant};
+
+mod resonance;
+
+use render::{Canvas, Cell, Colour, Renderer};
+use resonance::{all_antinodes, Bounds, Harmonics, Position};
 
 fn main() {
     let path = Path::new("input.txt");
     let input = fs::read_to_string(path).expect("Cannot solve without input!");
 
     let antennas = antennas(&input);
+    let bounds = bounds(&input);
 
     let start_1 = Instant::now();
     println!(
         "Part 1: {}, {:?}",
-        part_1(
-            &antennas,
-            input.lines().count(),
-            input.lines().next().unwrap().chars().count()
-        ),
+        part_1(&antennas, bounds),
         start_1.elapsed()
     );
     let start_2 = Instant::now();
     println!(
         "Part 2: {}, {:?}",
-        part_2(
-            &antennas,
-            input.lines().count(),
-            input.lines().next().unwrap().chars().count()
-        ),
+        part_2(&antennas, bounds),
         start_2.elapsed()
     );
-}
 
-fn part_1(antennas: &HashMap<char, Vec<(usize, usize)>>, height: usize, width: usize) -> usize {
-    let mut antinodes: HashSet<(usize, usize)> = HashSet::new();
-    antennas.values().for_each(|locations| {
-        get_antinodes(locations, &mut antinodes, height, width);
-    });
-    antinodes.len()
+    if env::args().any(|arg| arg == "--render") {
+        let status = format!(
+            "{} antinodes, {} with resonant harmonics",
+            part_1(&antennas, bounds),
+            part_2(&antennas, bounds)
+        );
+        Renderer::new()
+            .legend(ANTINODE, "antinode")
+            .legend(HARMONIC, "resonant harmonic")
+            .legend(Cell::new('a').bg(Colour::Red), "antenna on an antinode")
+            .draw(&canvas(&antennas, bounds), &status)
+            .expect("Cannot write to stdout!");
+    }
 }
 
-fn part_2(antennas: &HashMap<char, Vec<(usize, usize)>>, height: usize, width: usize) -> usize {
-    let mut antinodes: HashSet<(usize, usize)> = HashSet::new();
-    antennas.values().for_each(|locations| {
-        get_antinodes_2(locations, &mut antinodes, height, width);
-    });
-    antinodes.len()
-}
+const ANTINODE: Cell = Cell::new('#').fg(Colour::BrightRed);
+const HARMONIC: Cell = Cell::new('*').fg(Colour::Magenta);
 
-fn get_antinodes(
-    locations: &[(usize, usize)],
-    antinodes: &mut HashSet<(usize, usize)>,
-    height: usize,
-    width: usize,
-) {
-    for location in locations {
-        for other in locations {
-            if location == other {
-                continue;
-            }
-            if let Some(mirror) = get_mirror(*location, *other, height, width) {
-                antinodes.insert(mirror);
-            }
-        }
+/// Antennas coloured by frequency, with the antinodes of both parts
+fn canvas(antennas: &HashMap<char, Vec<Position>>, bounds: Bounds) -> Canvas {
+    let mut canvas = Canvas::new(bounds.width, bounds.height, Cell::EMPTY);
+    let first = all_antinodes(antennas, bounds, Harmonics::FIRST);
+    for (i, j) in all_antinodes(antennas, bounds, Harmonics::Line) {
+        canvas.set(
+            j,
+            i,
+            if first.contains(&(i, j)) {
+                ANTINODE
+            } else {
+                HARMONIC
+            },
+        );
     }
-}
 
-fn get_antinodes_2(
-    locations: &[(usize, usize)],
-    antinodes: &mut HashSet<(usize, usize)>,
-    height: usize,
-    width: usize,
-) {
-    for location in locations {
-        for other in locations {
-            if location == other {
-                continue;
-            }
-            let mut current = *location;
-            let mut point_to_mirror = *other;
-            antinodes.insert(current); // Make sure that the location of the antenna is included
-            antinodes.insert(point_to_mirror); // Same for the other antenna
-
-            while let Some(mirror) = get_mirror(current, point_to_mirror, height, width) {
-                antinodes.insert(mirror);
-                point_to_mirror = current;
-                current = mirror;
+    let mut frequencies: Vec<_> = antennas.keys().collect();
+    frequencies.sort();
+    for (n, frequency) in frequencies.into_iter().enumerate() {
+        for &(i, j) in &antennas[frequency] {
+            let mut cell = Cell::new(*frequency).fg(Colour::nth(n));
+            if first.contains(&(i, j)) {
+                cell = cell.fg(Colour::BrightWhite).bg(Colour::Red);
             }
+            canvas.set(j, i, cell);
         }
     }
+    canvas
 }
 
-fn get_mirror(
-    location: (usize, usize),
-    other: (usize, usize),
-    width: usize,
-    height: usize,
-) -> Option<(usize, usize)> {
-    let diff = (
-        location.0 as i32 - other.0 as i32,
-        location.1 as i32 - other.1 as i32,
-    );
-    let mirror = (location.0 as i32 + diff.0, location.1 as i32 + diff.1);
-    if mirror.0 < 0 || mirror.0 >= height as i32 || mirror.1 < 0 || mirror.1 >= width as i32 {
-        return None;
+fn part_1(antennas: &HashMap<char, Vec<Position>>, bounds: Bounds) -> usize {
+    all_antinodes(antennas, bounds, Harmonics::FIRST).len()
+}
+
+fn part_2(antennas: &HashMap<char, Vec<Position>>, bounds: Bounds) -> usize {
+    all_antinodes(antennas, bounds, Harmonics::Line).len()
+}
+
+fn bounds(input: &str) -> Bounds {
+    Bounds {
+        height: input.lines().count(),
+        width: input.lines().next().map_or(0, |line| line.chars().count()),
     }
-    Some((mirror.0 as usize, mirror.1 as usize))
 }
 
-fn antennas(input: &str) -> HashMap<char, Vec<(usize, usize)>> {
-    let mut antennas: HashMap<char, Vec<(usize, usize)>> = HashMap::new();
+fn antennas(input: &str) -> HashMap<char, Vec<Position>> {
+    let mut antennas: HashMap<char, Vec<Position>> = HashMap::new();
     for (i, row) in input.lines().enumerate() {
         for (j, char) in row.chars().enumerate() {
             match char {
@@ -148,19 +127,18 @@ mod tests {
     #[test]
     fn test_part_1() {
         let antennas = antennas(INPUT);
-        assert_eq!(part_1(&antennas, 12, 12), 14);
+        assert_eq!(part_1(&antennas, bounds(INPUT)), 14);
     }
 
     #[test]
     fn test_part_2() {
         let antennas = antennas(INPUT);
-        assert_eq!(part_2(&antennas, 12, 12), 34);
+        assert_eq!(part_2(&antennas, bounds(INPUT)), 34);
     }
 
     #[test]
     fn test_part_2_t() {
-        let antennas = antennas(
-            "T....#....
+        let input = "T....#....
 ...T......
 .T....#...
 .........#
@@ -169,8 +147,52 @@ mod tests {
 ...#......
 ..........
 ....#.....
-..........",
+..........";
+        let antennas = antennas(input);
+        assert_eq!(part_2(&antennas, bounds(input)), 9);
+    }
+
+    #[test]
+    fn test_non_square() {
+        let input = "..........\n...a..a...\n..........";
+        let antennas = antennas(input);
+        assert_eq!(bounds(input).width, 10);
+        assert_eq!(part_1(&antennas, bounds(input)), 2);
+        assert_eq!(part_2(&antennas, bounds(input)), 10);
+    }
+
+    #[test]
+    fn test_canvas() {
+        let input = "..........\n...a..a...\n..........";
+        let canvas = canvas(&antennas(input), bounds(input));
+        let rendered = Renderer::new().plain().render(&canvas, "");
+        assert_eq!(rendered, "..........\n#**a**a**#\n..........\n");
+    }
+
+    #[test]
+    fn test_lattice() {
+        let bounds = Bounds {
+            height: 5,
+            width: 7,
+        };
+        let mut line = resonance::antinodes_of_pair((0, 0), (2, 4), bounds, Harmonics::Line);
+        line.sort();
+        assert_eq!(line, vec![(0, 0), (1, 2), (2, 4), (3, 6)]);
+
+        let bounds = Bounds {
+            height: 1,
+            width: 20,
+        };
+        let mut range = resonance::antinodes_of_pair(
+            (0, 0),
+            (0, 1),
+            bounds,
+            Harmonics::Range {
+                from: 2,
+                to: Some(5),
+            },
         );
-        assert_eq!(part_2(&antennas, 10, 10), 9);
+        range.sort();
+        assert_eq!(range, vec![(0, 3), (0, 4), (0, 5), (0, 6)]);
     }
 }
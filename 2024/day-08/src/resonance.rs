@@ -0,0 +1,112 @@
+//! Antinodes created by pairs of antennas with the same frequency.
+
+use std::collections::{HashMap, HashSet};
+
+pub type Position = (usize, usize);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Bounds {
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Bounds {
+    fn get(&self, (i, j): (i64, i64)) -> Option<Position> {
+        if (0..self.height as i64).contains(&i) && (0..self.width as i64).contains(&j) {
+            Some((i as usize, j as usize))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Harmonics {
+    /// Every grid point exactly in line with the two antennas.
+    Line,
+    /// Points `n` antenna distances beyond each antenna, for `from <= n <= to`.
+    /// Without `to`, continues until the edge of the grid.
+    Range { from: u32, to: Option<u32> },
+}
+
+impl Harmonics {
+    /// The antinode on each side, as in part 1.
+    pub const FIRST: Self = Harmonics::Range {
+        from: 1,
+        to: Some(1),
+    };
+}
+
+/// All antinodes of all frequencies within `bounds`.
+pub fn all_antinodes(
+    antennas: &HashMap<char, Vec<Position>>,
+    bounds: Bounds,
+    harmonics: Harmonics,
+) -> HashSet<Position> {
+    let mut antinodes = HashSet::new();
+    for locations in antennas.values() {
+        for (n, &a) in locations.iter().enumerate() {
+            for &b in &locations[n + 1..] {
+                antinodes.extend(antinodes_of_pair(a, b, bounds, harmonics));
+            }
+        }
+    }
+    antinodes
+}
+
+/// Antinodes of the antennas `a` and `b` within `bounds`, in both directions.
+pub fn antinodes_of_pair(
+    a: Position,
+    b: Position,
+    bounds: Bounds,
+    harmonics: Harmonics,
+) -> Vec<Position> {
+    if a == b {
+        return Vec::new();
+    }
+    let a = (a.0 as i64, a.1 as i64);
+    let b = (b.0 as i64, b.1 as i64);
+    let delta = (a.0 - b.0, a.1 - b.1);
+
+    match harmonics {
+        Harmonics::Line => {
+            let divisor = gcd(delta.0.abs(), delta.1.abs());
+            let step = (delta.0 / divisor, delta.1 / divisor);
+            let mut antinodes = walk(a, step, 0, None, bounds);
+            antinodes.extend(walk(a, (-step.0, -step.1), 1, None, bounds));
+            antinodes
+        }
+        Harmonics::Range { from, to } => {
+            let mut antinodes = walk(a, delta, from, to, bounds);
+            antinodes.extend(walk(b, (-delta.0, -delta.1), from, to, bounds));
+            antinodes
+        }
+    }
+}
+
+/// Points `origin + n * step` for `from <= n <= to` that are within `bounds`.
+fn walk(
+    origin: (i64, i64),
+    step: (i64, i64),
+    from: u32,
+    to: Option<u32>,
+    bounds: Bounds,
+) -> Vec<Position> {
+    let mut points = Vec::new();
+    // Once the walk leaves the grid, it cannot come back
+    for n in (from as i64)..=to.map_or(i64::MAX, i64::from) {
+        match bounds.get((origin.0 + n * step.0, origin.1 + n * step.1)) {
+            Some(point) => points.push(point),
+            None => break,
+        }
+    }
+    points
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
@@ -0,0 +1,202 @@
+//! Disk made of spans of file blocks and free space.
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// Consecutive blocks belonging to one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct File {
+    pub id: usize,
+    pub start: usize,
+    pub length: usize,
+}
+
+/// Consecutive free blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    pub start: usize,
+    pub length: usize,
+}
+
+/// Blocks of a file moved during compaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub id: usize,
+    pub from: usize,
+    pub to: usize,
+    pub length: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Disk {
+    /// Files and file fragments, ordered by id
+    pub files: Vec<File>,
+    /// Free space, ordered by position
+    pub gaps: Vec<Gap>,
+    /// Total number of blocks
+    pub size: usize,
+}
+
+impl Disk {
+    /// Build the disk from the dense disk map format, e.g. `12345`.
+    pub fn new(map: &[u8]) -> Self {
+        let mut files = Vec::new();
+        let mut size = 0;
+        for (i, &length) in map.iter().enumerate() {
+            let length = length as usize;
+            if i % 2 == 0 {
+                files.push(File {
+                    id: i / 2,
+                    start: size,
+                    length,
+                });
+            }
+            size += length;
+        }
+        Self {
+            gaps: free_space(&files, size),
+            files,
+            size,
+        }
+    }
+
+    /// Move single blocks from the end of the disk to the leftmost free block,
+    /// until there are no gaps between file blocks. Returns the moves, with
+    /// consecutive blocks moved together merged into one.
+    pub fn compact_blocks(&mut self) -> Vec<Move> {
+        let mut files = std::mem::take(&mut self.files);
+        let mut fragments = Vec::new();
+        let mut moves = Vec::new();
+
+        for mut gap in self.gaps.drain(..) {
+            while gap.length > 0 {
+                let Some(last) = files.last_mut().filter(|file| file.start > gap.start) else {
+                    break;
+                };
+                let moved = gap.length.min(last.length);
+                moves.push(Move {
+                    id: last.id,
+                    from: last.start + last.length - moved,
+                    to: gap.start,
+                    length: moved,
+                });
+                fragments.push(File {
+                    id: last.id,
+                    start: gap.start,
+                    length: moved,
+                });
+                last.length -= moved;
+                gap.start += moved;
+                gap.length -= moved;
+                if last.length == 0 {
+                    files.pop();
+                }
+            }
+        }
+
+        files.extend(fragments);
+        files.sort_by_key(|file| (file.id, file.start));
+        self.gaps = free_space(&files, self.size);
+        self.files = files;
+        moves
+    }
+
+    /// Move whole files, starting from the highest id, to the leftmost gap
+    /// that fits them. Each file is moved at most once. Returns the moves.
+    pub fn compact_files(&mut self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        // `heaps[length]` contains the starts of all gaps of that length
+        let max_length = self.gaps.iter().map(|gap| gap.length).max().unwrap_or(0);
+        let mut heaps: Vec<BinaryHeap<Reverse<usize>>> = vec![BinaryHeap::new(); max_length + 1];
+        for gap in &self.gaps {
+            heaps[gap.length].push(Reverse(gap.start));
+        }
+
+        // Space freed by a moved file is right of every file still to be moved,
+        // so it never needs to be added to the heaps
+        for file in self.files.iter_mut().rev() {
+            let Some((length, start)) = heaps
+                .iter()
+                .enumerate()
+                .skip(file.length)
+                .filter_map(|(length, heap)| heap.peek().map(|&Reverse(start)| (length, start)))
+                .filter(|&(_, start)| start < file.start)
+                .min_by_key(|&(_, start)| start)
+            else {
+                continue;
+            };
+
+            heaps[length].pop();
+            if length > file.length {
+                heaps[length - file.length].push(Reverse(start + file.length));
+            }
+            moves.push(Move {
+                id: file.id,
+                from: file.start,
+                to: start,
+                length: file.length,
+            });
+            file.start = start;
+        }
+
+        self.gaps = free_space(&self.files, self.size);
+        moves
+    }
+
+    /// The disk in the puzzle format, e.g. `00...111...2...`, cut to `limit`
+    /// blocks. Ids are shown by their last digit.
+    pub fn render(&self, limit: usize) -> String {
+        let shown = self.size.min(limit);
+        let mut blocks = vec!['.'; shown];
+        for file in &self.files {
+            let digit = char::from_digit((file.id % 10) as u32, 10).unwrap();
+            for block in blocks
+                .iter_mut()
+                .take(file.start + file.length)
+                .skip(file.start)
+            {
+                *block = digit;
+            }
+        }
+        let mut rendered: String = blocks.into_iter().collect();
+        if self.size > shown {
+            rendered.push_str(&format!(" [{} more blocks]", self.size - shown));
+        }
+        rendered
+    }
+
+    pub fn checksum(&self) -> usize {
+        self.files
+            .iter()
+            .map(|file| file.id * (file.start..file.start + file.length).sum::<usize>())
+            .sum()
+    }
+}
+
+/// Free space not covered by `files` on a disk of `size` blocks, ordered by position.
+fn free_space(files: &[File], size: usize) -> Vec<Gap> {
+    let mut spans: Vec<(usize, usize)> = files
+        .iter()
+        .filter(|file| file.length > 0)
+        .map(|file| (file.start, file.length))
+        .collect();
+    spans.sort();
+
+    let mut gaps = Vec::new();
+    let mut end = 0;
+    for (start, length) in spans {
+        if start > end {
+            gaps.push(Gap {
+                start: end,
+                length: start - end,
+            });
+        }
+        end = end.max(start + length);
+    }
+    if size > end {
+        gaps.push(Gap {
+            start: end,
+            length: size - end,
+        });
+    }
+    gaps
+}
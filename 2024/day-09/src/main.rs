@@ -1,4 +1,8 @@
-use std::{fs, path::Path, time::Instant};
+use std::{env, fs, path::Path, time::Instant};
+
+mod disk;
+
+use disk::{Disk, Move};
 
 fn main() {
     let path = Path::new("input.txt");
@@ -13,96 +17,60 @@ fn main() {
     println!("Part 1: {}, {:?}", part_1(&input), start_1.elapsed());
     let start_2 = Instant::now();
     println!("Part 2: {}, {:?}", part_2(&input), start_2.elapsed());
-}
 
-fn part_1(input: &Vec<u8>) -> usize {
-    let mut disk = get_disk(input);
-    let mut ptr = 0;
-    let mut tail_ptr = disk.len() - 1;
-    let mut checksum = 0;
-
-    while ptr <= tail_ptr {
-        if let Some(id) = disk[ptr] {
-            checksum += ptr * id;
-            ptr += 1;
-        } else {
-            disk.swap(ptr, tail_ptr);
-            checksum += ptr * disk[ptr].unwrap();
-            ptr += 1;
-            while disk[tail_ptr].is_none() && ptr <= tail_ptr {
-                tail_ptr -= 1;
-            }
+    // `--visualize [LIMIT]` shows at most LIMIT blocks and moves, the next
+    // argument is only the limit if it is a number
+    let args: Vec<String> = env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--visualize") {
+        let limit = args
+            .get(index + 1)
+            .and_then(|limit| limit.parse().ok())
+            .unwrap_or(VISUALIZE_LIMIT);
+        let compactions: [(&str, Compaction); 2] = [
+            ("Part 1, moving blocks", Disk::compact_blocks),
+            ("Part 2, moving files", Disk::compact_files),
+        ];
+        for (title, compact) in compactions {
+            print!("\n{title}\n{}", visualize(&input, compact, limit));
         }
     }
-    checksum
 }
 
-fn part_2(input: &Vec<u8>) -> usize {
-    let mut disk = get_disk(input);
-    let mut tail_ptr = disk.len() - 1;
-    let mut current_file = disk[tail_ptr];
-    let mut current_file_len = 0;
-
-    while 0 < tail_ptr {
-        // Find current file location and length
-        while disk[tail_ptr] == current_file && tail_ptr > 0 {
-            tail_ptr -= 1;
-            current_file_len += 1;
-        }
+const VISUALIZE_LIMIT: usize = 100;
 
-        // Search for a spot
-        let mut first_none = None;
-        let mut ptr = 0;
-        while ptr <= tail_ptr {
-            if disk[ptr].is_none() {
-                if first_none.is_none() {
-                    first_none = Some(ptr);
-                }
-                // Found a spot, move the whole file
-                if ptr + 1 - first_none.unwrap() >= current_file_len {
-                    for i in 0..current_file_len {
-                        disk.swap(first_none.unwrap() + i, tail_ptr + 1 + i);
-                    }
-                    break;
-                }
-            } else {
-                first_none = None;
-            }
-            ptr += 1;
-        }
+/// One of the ways to compact a disk, returning the moves made
+type Compaction = fn(&mut Disk) -> Vec<Move>;
 
-        // Find next file
-        while disk[tail_ptr].is_none() && tail_ptr > 0 {
-            tail_ptr -= 1;
-        }
-        current_file = disk[tail_ptr];
-        current_file_len = 0;
+/// The disk before and after compaction, and the moves in between
+fn visualize(input: &[u8], compact: Compaction, limit: usize) -> String {
+    let mut disk = Disk::new(input);
+    let before = disk.render(limit);
+    let moves = compact(&mut disk);
+
+    let mut output = format!("Before: {before}\n");
+    for step in moves.iter().take(limit) {
+        output.push_str(&format!(
+            "  File {}: {} -> {}, length {}\n",
+            step.id, step.from, step.to, step.length
+        ));
     }
-    checksum(&disk)
+    if moves.len() > limit {
+        output.push_str(&format!("  ... {} more moves\n", moves.len() - limit));
+    }
+    output.push_str(&format!("After:  {}\n", disk.render(limit)));
+    output
 }
 
-fn checksum(disk: &[Option<usize>]) -> usize {
-    let mut checksum = 0;
-    for (i, block) in disk.iter().enumerate() {
-        if let Some(id) = block {
-            checksum += id * i;
-        }
-    }
-    checksum
+fn part_1(input: &[u8]) -> usize {
+    let mut disk = Disk::new(input);
+    disk.compact_blocks();
+    disk.checksum()
 }
 
-fn get_disk(input: &Vec<u8>) -> Vec<Option<usize>> {
-    let mut disk: Vec<Option<usize>> = Vec::new();
-    for (id, block) in input.iter().enumerate() {
-        for _ in 0..*block {
-            if id % 2 == 0 {
-                disk.push(Some(id / 2));
-            } else {
-                disk.push(None);
-            }
-        }
-    }
-    disk
+fn part_2(input: &[u8]) -> usize {
+    let mut disk = Disk::new(input);
+    disk.compact_files();
+    disk.checksum()
 }
 
 #[cfg(test)]
@@ -128,4 +96,76 @@ mod tests {
             .collect();
         assert_eq!(part_2(&input), 2858);
     }
+
+    #[test]
+    fn test_visualize() {
+        let input: Vec<u8> = "2333133121414131402"
+            .chars()
+            .map(|c| c.to_digit(10).unwrap() as u8)
+            .collect();
+        let disk = Disk::new(&input);
+        assert_eq!(
+            disk.render(100),
+            "00...111...2...333.44.5555.6666.777.888899"
+        );
+        assert_eq!(disk.render(5), "00... [37 more blocks]");
+
+        let output = visualize(&input, Disk::compact_files, 2);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Before: 00 [40 more blocks]");
+        assert_eq!(lines[1], "  File 9: 40 -> 2, length 2");
+        assert_eq!(lines[2], "  File 7: 32 -> 8, length 3");
+        assert_eq!(lines[3], "  ... 2 more moves");
+
+        let output = visualize(&input, Disk::compact_blocks, 100);
+        assert!(output.ends_with("After:  0099811188827773336446555566..............\n"));
+    }
+
+    #[test]
+    fn test_compact_files() {
+        use disk::{File, Gap};
+        let mut disk = Disk::new(&[1, 2, 3, 4, 2]);
+        assert_eq!(disk.size, 12);
+        assert_eq!(
+            disk.gaps,
+            vec![
+                Gap {
+                    start: 1,
+                    length: 2
+                },
+                Gap {
+                    start: 6,
+                    length: 4
+                }
+            ]
+        );
+        disk.compact_files();
+        assert_eq!(
+            disk.files,
+            vec![
+                File {
+                    id: 0,
+                    start: 0,
+                    length: 1
+                },
+                File {
+                    id: 1,
+                    start: 3,
+                    length: 3
+                },
+                File {
+                    id: 2,
+                    start: 1,
+                    length: 2
+                }
+            ]
+        );
+        assert_eq!(
+            disk.gaps,
+            vec![Gap {
+                start: 6,
+                length: 6
+            }]
+        );
+    }
 }
@@ -1,9 +1,9 @@
 use std::{
-    collections::{HashSet, VecDeque},
-    fs,
+    collections::{HashMap, HashSet},
+    env, fs,
     path::Path,
+    str::FromStr,
     time::Instant,
-    usize,
 };
 
 struct Graph {
@@ -32,8 +32,8 @@ impl Graph {
         }
     }
 
-    /// Neighbout nodes, ie next to (i, j), within grid bounds and value is one higher
-    fn neighbours(&self, i: usize, j: usize) -> Vec<(usize, usize)> {
+    /// Neighbour nodes, ie next to (i, j), within grid bounds and reachable with `rule`
+    fn neighbours(&self, i: usize, j: usize, rule: &TrailRule) -> Vec<(usize, usize)> {
         let mut neighbours = Vec::new();
         let value = self.data[i][j];
         for direction in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
@@ -47,7 +47,7 @@ impl Graph {
                 continue;
             }
 
-            if self.data[new_i as usize][new_j as usize] != value + 1 {
+            if !rule.allows(value, self.data[new_i as usize][new_j as usize]) {
                 continue;
             }
 
@@ -55,77 +55,154 @@ impl Graph {
         }
         neighbours
     }
-}
-
-fn main() {
-    let path = Path::new("input.txt");
-    let input = fs::read_to_string(path).expect("Cannot solve without input!");
-    let graph = Graph::from_str(&input);
 
-    let start_1 = Instant::now();
-    println!("Part 1: {}, {:?}", part_1(&graph), start_1.elapsed());
-    let start_2 = Instant::now();
-    println!("Part 2: {}, {:?}", part_2(&graph), start_2.elapsed());
-}
+    /// Score and rating of every trailhead, computed from the trail ends backwards.
+    fn trails(&self, rule: &TrailRule) -> Trails {
+        let mut cells: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|i| (0..self.width).map(move |j| (i, j)))
+            .filter(|&(i, j)| rule.contains(self.data[i][j]))
+            .collect();
+        // Every step moves towards `end`, so cells closest to it are finished first
+        cells.sort_by_key(|&(i, j)| self.data[i][j].abs_diff(rule.end));
 
-fn part_1(graph: &Graph) -> u32 {
-    let mut sum = 0;
-    for i in 0..graph.data.len() {
-        for j in 0..graph.data[i].len() {
-            if graph.data[i][j] == 0 {
-                sum += trailhead_score(&graph, i, j);
+        let mut ratings: HashMap<(usize, usize), u64> = HashMap::new();
+        let mut summits: HashMap<(usize, usize), HashSet<(usize, usize)>> = HashMap::new();
+        for (i, j) in cells {
+            if self.data[i][j] == rule.end {
+                ratings.insert((i, j), 1);
+                summits.insert((i, j), HashSet::from([(i, j)]));
+                continue;
+            }
+            let mut rating = 0;
+            let mut reachable = HashSet::new();
+            for neighbour in self.neighbours(i, j, rule) {
+                rating += ratings.get(&neighbour).copied().unwrap_or(0);
+                if let Some(neighbour_summits) = summits.get(&neighbour) {
+                    reachable.extend(neighbour_summits);
+                }
             }
+            ratings.insert((i, j), rating);
+            summits.insert((i, j), reachable);
+        }
+
+        let is_trailhead = |&(i, j): &(usize, usize)| self.data[i][j] == rule.start;
+        Trails {
+            scores: summits
+                .into_iter()
+                .filter(|(position, _)| is_trailhead(position))
+                .map(|(position, reachable)| (position, reachable.len()))
+                .collect(),
+            ratings: ratings
+                .into_iter()
+                .filter(|(position, _)| is_trailhead(position))
+                .collect(),
         }
     }
-    sum
 }
 
-fn part_2(graph: &Graph) -> u32 {
-    let mut sum = 0;
-    for i in 0..graph.data.len() {
-        for j in 0..graph.data[i].len() {
-            if graph.data[i][j] == 0 {
-                sum += trailhead_score_2(&graph, i, j);
-            }
+/// Which heights a trail goes through, and how it may step between them.
+struct TrailRule {
+    start: u8,
+    end: u8,
+    step: Step,
+}
+
+#[derive(Clone, Copy)]
+enum Step {
+    /// Height changes by exactly this much, e.g. `1` or `-1`
+    Exact(i8),
+    /// Height changes by any amount towards the end of the trail
+    Any,
+}
+
+impl TrailRule {
+    const HIKING: Self = TrailRule {
+        start: 0,
+        end: 9,
+        step: Step::Exact(1),
+    };
+
+    fn contains(&self, height: u8) -> bool {
+        (self.start.min(self.end)..=self.start.max(self.end)).contains(&height)
+    }
+
+    fn allows(&self, from: u8, to: u8) -> bool {
+        if !self.contains(to) {
+            return false;
+        }
+        let difference = to as i16 - from as i16;
+        let towards_end = (self.end as i16 - from as i16).signum();
+        match self.step {
+            Step::Exact(step) => step != 0 && difference == step as i16,
+            Step::Any => difference != 0 && difference.signum() == towards_end,
         }
     }
-    sum
 }
 
-/// BFS, if node == 9, increment score
-fn trailhead_score(graph: &Graph, i: usize, j: usize) -> u32 {
-    let mut score = 0;
-    let mut queue = VecDeque::from([(i, j)]);
-    let mut visited = HashSet::from([(i, j)]);
-    while !queue.is_empty() {
-        let node = queue.pop_front().expect("while !queue.is_empty()");
-        if graph.data[node.0][node.1] == 9 {
-            score += 1;
-        }
-        for neighbour in graph.neighbours(node.0, node.1) {
-            if visited.get(&neighbour).is_none() {
-                visited.insert(neighbour);
-                queue.push_back(neighbour);
+impl FromStr for TrailRule {
+    type Err = String;
+
+    /// Parse `START..END[,STEP]`, where `STEP` is a height difference or `any`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (range, step) = s.split_once(',').unwrap_or((s, "1"));
+        let (start, end) = range
+            .split_once("..")
+            .ok_or_else(|| format!("Invalid range: {range}"))?;
+        let parse_height = |height: &str| {
+            height
+                .parse::<u8>()
+                .map_err(|_| format!("Invalid height: {height}"))
+        };
+        let step = match step {
+            "any" => Step::Any,
+            step => Step::Exact(step.parse().map_err(|_| format!("Invalid step: {step}"))?),
+        };
+        let (start, end) = (parse_height(start)?, parse_height(end)?);
+        // Trails are scored from the end backwards, so each step has to head there
+        if let Step::Exact(step) = step {
+            if step.signum() as i16 != (end as i16 - start as i16).signum() {
+                return Err(format!("Step {step} does not lead from {start} to {end}"));
             }
         }
+        Ok(TrailRule { start, end, step })
     }
-    score
 }
 
-/// BFS without visited, goes through all possible paths to node == 9
-fn trailhead_score_2(graph: &Graph, i: usize, j: usize) -> u32 {
-    let mut score = 0;
-    let mut queue = VecDeque::from([(i, j)]);
-    while !queue.is_empty() {
-        let node = queue.pop_front().expect("while !queue.is_empty()");
-        if graph.data[node.0][node.1] == 9 {
-            score += 1;
-        }
-        for neighbour in graph.neighbours(node.0, node.1) {
-            queue.push_back(neighbour);
-        }
+struct Trails {
+    /// Number of distinct trail ends reachable from each trailhead
+    scores: HashMap<(usize, usize), usize>,
+    /// Number of distinct trails starting from each trailhead
+    ratings: HashMap<(usize, usize), u64>,
+}
+
+fn main() {
+    let path = Path::new("input.txt");
+    let input = fs::read_to_string(path).expect("Cannot solve without input!");
+    let graph = Graph::from_str(&input);
+
+    let start_1 = Instant::now();
+    println!("Part 1: {}, {:?}", part_1(&graph), start_1.elapsed());
+    let start_2 = Instant::now();
+    println!("Part 2: {}, {:?}", part_2(&graph), start_2.elapsed());
+
+    // Optionally explore other kinds of trails, e.g. `cargo run -- 9..0,-1`
+    if let Some(rule) = env::args().nth(1) {
+        let rule: TrailRule = rule.parse().expect("Rule should be START..END[,STEP|any]");
+        let trails = graph.trails(&rule);
+        println!(
+            "Custom: score {}, rating {}",
+            trails.scores.values().sum::<usize>(),
+            trails.ratings.values().sum::<u64>()
+        );
     }
-    score
+}
+
+fn part_1(graph: &Graph) -> usize {
+    graph.trails(&TrailRule::HIKING).scores.values().sum()
+}
+
+fn part_2(graph: &Graph) -> u64 {
+    graph.trails(&TrailRule::HIKING).ratings.values().sum()
 }
 
 #[cfg(test)]
@@ -171,4 +248,41 @@ mod tests {
         );
         assert_eq!(81, part_2(&graph));
     }
+
+    #[test]
+    fn test_trails() {
+        let graph = Graph::from_str(
+            "5550555
+5551555
+5552555
+6543456
+7555557
+8555558
+9555559",
+        );
+        let trails = graph.trails(&TrailRule::HIKING);
+        assert_eq!(trails.scores, HashMap::from([((0, 3), 2)]));
+        assert_eq!(trails.ratings, HashMap::from([((0, 3), 2)]));
+
+        let descending: TrailRule = "9..0,-1".parse().unwrap();
+        let trails = graph.trails(&descending);
+        assert_eq!(trails.scores, HashMap::from([((6, 0), 1), ((6, 6), 1)]));
+
+        let any_increase: TrailRule = "0..9,any".parse().unwrap();
+        let graph = Graph::from_str("05\n19");
+        let trails = graph.trails(&any_increase);
+        assert_eq!(trails.scores[&(0, 0)], 1);
+        assert_eq!(trails.ratings[&(0, 0)], 2);
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        assert!("0..9".parse::<TrailRule>().is_ok());
+        assert!("9..0,-1".parse::<TrailRule>().is_ok());
+        assert!("0..9,-1".parse::<TrailRule>().is_err());
+        assert!("9..0,1".parse::<TrailRule>().is_err());
+        assert!("0..9,0".parse::<TrailRule>().is_err());
+        assert!("3..3,1".parse::<TrailRule>().is_err());
+        assert!("0..9,x".parse::<TrailRule>().is_err());
+    }
 }
@@ -1,4 +1,12 @@
-use std::{collections::HashMap, fs, path::Path, time::Instant};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    env, fmt, fs,
+    path::Path,
+    str::FromStr,
+    time::Instant,
+};
+
+use num_bigint::BigUint;
 
 fn main() {
     let path = Path::new("input.txt");
@@ -7,46 +15,149 @@ fn main() {
         .split_whitespace()
         .map(|n| n.parse::<u128>().unwrap())
         .collect::<Vec<u128>>();
+    let args: Vec<String> = env::args().collect();
 
-    let start_1 = Instant::now();
-    println!("Part 1: {}, {:?}", solve(&input, 25), start_1.elapsed());
-    let start_2 = Instant::now();
-    println!("Part 2: {}, {:?}", solve(&input, 75), start_2.elapsed());
-    let start_3 = Instant::now();
-    println!(
-        "Part 3 (just for fun): {}, {:?}",
-        solve(&input, 207),
-        start_3.elapsed()
-    );
-}
-
-fn solve(input: &[u128], depth: u8) -> u128 {
-    let mut cache: HashMap<(u128, u8), u128> = HashMap::new();
-    let mut length = 0;
-    for stone in input {
-        length += blink(*stone, depth, &mut cache);
-    }
-    length
-}
-
-fn blink(stone: u128, depth: u8, cache: &mut HashMap<(u128, u8), u128>) -> u128 {
-    if depth == 0 {
-        return 1;
-    }
-    if let Some(len) = cache.get(&(stone, depth)) {
-        return *len;
-    }
-    let result = if stone == 0 {
-        blink(1, depth - 1, cache)
-    } else if (stone.checked_ilog10().unwrap_or(0) + 1) % 2 == 0 {
-        let number_string = stone.to_string();
-        let (a, b) = number_string.split_at(number_string.len() / 2);
-        blink(a.parse().unwrap(), depth - 1, cache) + blink(b.parse().unwrap(), depth - 1, cache)
-    } else {
-        blink(stone * 2024, depth - 1, cache)
+    // Variants of the puzzle from `--rules`, e.g. `--rules '0->1,split,*2024'`
+    let rules = match args.iter().position(|arg| arg == "--rules") {
+        Some(index) => parse_rules(args.get(index + 1).expect("Rules should be given"))
+            .unwrap_or_else(|error| panic!("{error}")),
+        None => BLINK_RULES.to_vec(),
     };
-    cache.insert((stone, depth), result);
-    result
+
+    for (name, depth) in [
+        ("Part 1", 25),
+        ("Part 2", 75),
+        ("Part 3 (just for fun)", 207),
+    ] {
+        let start = Instant::now();
+        match solve(&input, depth, &rules) {
+            Ok(count) => println!("{name}: {count}, {:?}", start.elapsed()),
+            Err(overflow) => println!("{name}: {overflow}, {:?}", start.elapsed()),
+        }
+    }
+}
+
+/// What happens to a stone when blinking. The first rule that applies is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rule {
+    /// Stones engraved with `from` are replaced with `to`
+    Replace { from: u128, to: u128 },
+    /// Stones with an even number of digits are split into left and right halves
+    SplitEvenDigits,
+    /// Stone is replaced with one multiplied by the factor
+    Multiply(u128),
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    /// Parse `FROM->TO`, `split` or `*FACTOR`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid number {value} in rule {s}"))
+        };
+        match s.trim() {
+            "split" => Ok(Rule::SplitEvenDigits),
+            rule if rule.starts_with('*') => Ok(Rule::Multiply(number(&rule[1..])?)),
+            rule => match rule.split_once("->") {
+                Some((from, to)) => Ok(Rule::Replace {
+                    from: number(from)?,
+                    to: number(to)?,
+                }),
+                None => Err(format!(
+                    "Unknown rule {s}, expected FROM->TO, split or *FACTOR"
+                )),
+            },
+        }
+    }
+}
+
+/// Parse a comma separated list of rules, in the order they are tried.
+fn parse_rules(s: &str) -> Result<Vec<Rule>, String> {
+    s.split(',').map(str::parse).collect()
+}
+
+/// A stone grew past `u128`, e.g. with a large factor or many blinks.
+#[derive(Debug, PartialEq, Eq)]
+struct Overflow {
+    stone: u128,
+    factor: u128,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "stone {} multiplied by {} does not fit in u128",
+            self.stone, self.factor
+        )
+    }
+}
+
+const BLINK_RULES: &[Rule] = &[
+    Rule::Replace { from: 0, to: 1 },
+    Rule::SplitEvenDigits,
+    Rule::Multiply(2024),
+];
+
+impl Rule {
+    /// Stones replacing `stone`, `None` if the rule does not apply.
+    fn apply(&self, stone: u128) -> Result<Option<Vec<u128>>, Overflow> {
+        match *self {
+            Rule::Replace { from, to } => Ok((stone == from).then(|| vec![to])),
+            Rule::SplitEvenDigits => {
+                let digits = stone.checked_ilog10().unwrap_or(0) + 1;
+                if digits.is_multiple_of(2) {
+                    let half = 10_u128.pow(digits / 2);
+                    Ok(Some(vec![stone / half, stone % half]))
+                } else {
+                    Ok(None)
+                }
+            }
+            Rule::Multiply(factor) => match stone.checked_mul(factor) {
+                Some(stone) => Ok(Some(vec![stone])),
+                None => Err(Overflow { stone, factor }),
+            },
+        }
+    }
+}
+
+/// Number of stones after blinking `depth` times.
+fn solve(input: &[u128], depth: u32, rules: &[Rule]) -> Result<BigUint, Overflow> {
+    let mut counts: HashMap<u128, BigUint> = HashMap::new();
+    for &stone in input {
+        *counts.entry(stone).or_default() += 1_u32;
+    }
+
+    // The same stones keep reappearing, so their successors are only computed once
+    let mut successors: HashMap<u128, Vec<u128>> = HashMap::new();
+    for _ in 0..depth {
+        let mut next: HashMap<u128, BigUint> = HashMap::with_capacity(counts.len());
+        for (stone, count) in counts {
+            let stones = match successors.entry(stone) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(blink(stone, rules)?),
+            };
+            for &new_stone in stones.iter() {
+                *next.entry(new_stone).or_default() += &count;
+            }
+        }
+        counts = next;
+    }
+    Ok(counts.into_values().sum())
+}
+
+/// Stones replacing `stone`, unchanged if no rule applies.
+fn blink(stone: u128, rules: &[Rule]) -> Result<Vec<u128>, Overflow> {
+    for rule in rules {
+        if let Some(stones) = rule.apply(stone)? {
+            return Ok(stones);
+        }
+    }
+    Ok(vec![stone])
 }
 
 #[cfg(test)]
@@ -56,6 +167,42 @@ mod tests {
     #[test]
     fn test_solve() {
         let stones = vec![125, 17];
-        assert_eq!(solve(&stones, 25), 55312);
+        assert_eq!(
+            solve(&stones, 25, BLINK_RULES),
+            Ok(BigUint::from(55312_u32))
+        );
+    }
+
+    #[test]
+    fn test_solve_overflow() {
+        let rules = parse_rules("*1000000000000000000000").unwrap();
+        assert_eq!(
+            solve(&[7], 3, &rules),
+            Err(Overflow {
+                stone: 7_000_000_000_000_000_000_000,
+                factor: 1_000_000_000_000_000_000_000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_blink() {
+        assert_eq!(blink(0, BLINK_RULES), Ok(vec![1]));
+        assert_eq!(blink(1000, BLINK_RULES), Ok(vec![10, 0]));
+        assert_eq!(blink(17, BLINK_RULES), Ok(vec![1, 7]));
+        assert_eq!(blink(999, BLINK_RULES), Ok(vec![2021976]));
+        assert_eq!(blink(5, &[Rule::SplitEvenDigits]), Ok(vec![5]));
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = parse_rules("0->1, split, *2024").unwrap();
+        assert_eq!(rules, BLINK_RULES);
+        assert_eq!(
+            parse_rules("1->7,*3").unwrap(),
+            vec![Rule::Replace { from: 1, to: 7 }, Rule::Multiply(3)]
+        );
+        assert!(parse_rules("double").is_err());
+        assert!(parse_rules("*two").is_err());
     }
 }
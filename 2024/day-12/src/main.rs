@@ -1,10 +1,12 @@
 use std::{
     collections::{HashSet, VecDeque},
-    fs,
+    env, fmt, fs,
     path::Path,
     time::Instant,
 };
 
+use render::{Canvas, Cell, Colour, Renderer};
+
 static DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
 
 struct Graph {
@@ -53,73 +55,149 @@ impl Graph {
         neighbours
     }
 
-    /// Count fences which visited neighbours have not counted yet
-    fn fence_cost(&self, i: usize, j: usize, visited: &mut HashSet<(usize, usize)>) -> u32 {
-        let mut fences = self.fences(i, j);
-        for neighbour in self.neighbours(i, j) {
-            if visited.get(&neighbour).is_some() {
-                fences.retain(|fence| self.fences(neighbour.0, neighbour.1).get(fence).is_none());
+    /// All regions of the garden, in the order their first cell appears
+    fn regions(&self) -> Vec<Region> {
+        let mut regions = Vec::new();
+        let mut visited = vec![vec![false; self.width]; self.height];
+        for i in 0..self.height {
+            for j in 0..self.width {
+                if !visited[i][j] {
+                    regions.push(self.region(i, j, &mut visited));
+                }
             }
         }
-        fences.len() as u32
-    }
-
-    /// Get directions where the fences are, ie. where there are no neighbours
-    fn fences(&self, i: usize, j: usize) -> HashSet<(i32, i32)> {
-        let mut fences = HashSet::from(DIRECTIONS);
-        for neighbour in self.neighbours(i, j) {
-            let neighbour_direction =
-                (neighbour.0 as i32 - i as i32, neighbour.1 as i32 - j as i32);
-            fences.retain(|dir| *dir != neighbour_direction);
-        }
-        fences
+        regions
     }
 
-    /// BFS with area and perimeter calculation
-    fn plot_cost(&self, i: usize, j: usize, visited: &mut HashSet<(usize, usize)>) -> u32 {
-        let mut area = 0;
+    /// BFS collecting the region containing (i, j)
+    fn region(&self, i: usize, j: usize, visited: &mut [Vec<bool>]) -> Region {
+        let mut cells = Vec::new();
         let mut perimeter = 0;
-        visited.insert((i, j));
+        visited[i][j] = true;
         let mut queue = VecDeque::from([(i, j)]);
-        while !queue.is_empty() {
-            let node = queue.pop_front().expect("while !queue.is_empty()");
+        while let Some(node) = queue.pop_front() {
             let neighbours = self.neighbours(node.0, node.1);
-            area += 1;
-            perimeter += 4 - neighbours.len() as u32;
-            for neighbour in neighbours {
-                if visited.get(&neighbour).is_none() {
-                    visited.insert(neighbour);
-                    queue.push_back(neighbour);
+            cells.push(node);
+            perimeter += 4 - neighbours.len();
+            for (n_i, n_j) in neighbours {
+                if !visited[n_i][n_j] {
+                    visited[n_i][n_j] = true;
+                    queue.push_back((n_i, n_j));
                 }
             }
         }
-        area * perimeter
+        cells.sort();
+
+        let members: HashSet<(i32, i32)> =
+            cells.iter().map(|&(i, j)| (i as i32, j as i32)).collect();
+        Region {
+            label: self.data[i][j] as char,
+            sides: corners(&members),
+            holes: holes(&members),
+            area: cells.len(),
+            perimeter,
+            cells,
+        }
     }
+}
 
-    /// BFS with area and custom perimeter calculation
-    /// `visited.insert(...)` moved to prevent messing up `self.fence_cost` result
-    fn plot_cost_2(&self, i: usize, j: usize, visited: &mut HashSet<(usize, usize)>) -> u32 {
-        let mut area = 0;
-        let mut perimeter = 0;
-        let mut queue = VecDeque::from([(i, j)]);
-        while !queue.is_empty() {
-            let node = queue.pop_front().expect("while !queue.is_empty()");
-            if visited.get(&node).is_some() {
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Region {
+    label: char,
+    /// Cells of the region as (i, j), sorted
+    cells: Vec<(usize, usize)>,
+    area: usize,
+    perimeter: usize,
+    sides: usize,
+    /// Number of areas fully enclosed by this region
+    holes: usize,
+}
+
+impl fmt::Display for Region {
+    /// Summary line followed by the shape of the region
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: area {}, perimeter {}, sides {}, holes {}",
+            self.label, self.area, self.perimeter, self.sides, self.holes
+        )?;
+        let min_i = self.cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+        let max_i = self.cells.iter().map(|cell| cell.0).max().unwrap_or(0);
+        let min_j = self.cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+        let max_j = self.cells.iter().map(|cell| cell.1).max().unwrap_or(0);
+        for i in min_i..=max_i {
+            let row: String = (min_j..=max_j)
+                .map(|j| {
+                    if self.cells.binary_search(&(i, j)).is_ok() {
+                        self.label
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            writeln!(f, "{row}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of corners in the region, which equals its number of sides
+fn corners(cells: &HashSet<(i32, i32)>) -> usize {
+    let mut corners = 0;
+    for &(i, j) in cells {
+        for (di, dj) in [(-1, -1), (-1, 1), (1, -1), (1, 1)] {
+            let vertical = cells.contains(&(i + di, j));
+            let horizontal = cells.contains(&(i, j + dj));
+            let diagonal = cells.contains(&(i + di, j + dj));
+            // Outer corner, or inner corner of an L-shape
+            if (!vertical && !horizontal) || (vertical && horizontal && !diagonal) {
+                corners += 1;
+            }
+        }
+    }
+    corners
+}
+
+/// Number of areas fully enclosed by the region.
+///
+/// Flood fills the outside within the bounding box padded by one cell. The
+/// outside is 8-connected, since the region itself is only 4-connected.
+fn holes(cells: &HashSet<(i32, i32)>) -> usize {
+    let min_i = cells.iter().map(|cell| cell.0).min().unwrap_or(0) - 1;
+    let max_i = cells.iter().map(|cell| cell.0).max().unwrap_or(0) + 1;
+    let min_j = cells.iter().map(|cell| cell.1).min().unwrap_or(0) - 1;
+    let max_j = cells.iter().map(|cell| cell.1).max().unwrap_or(0) + 1;
+
+    let mut components = 0;
+    let mut visited: HashSet<(i32, i32)> = HashSet::new();
+    for i in min_i..=max_i {
+        for j in min_j..=max_j {
+            if cells.contains(&(i, j)) || visited.contains(&(i, j)) {
                 continue;
             }
-            visited.insert(node);
-            let neighbours = self.neighbours(node.0, node.1);
-            area += 1;
-            perimeter += self.fence_cost(node.0, node.1, visited);
-            for neighbour in neighbours {
-                if visited.get(&neighbour).is_none() {
-                    queue.push_back(neighbour);
+            components += 1;
+            visited.insert((i, j));
+            let mut queue = VecDeque::from([(i, j)]);
+            while let Some((i, j)) = queue.pop_front() {
+                for di in -1..=1 {
+                    for dj in -1..=1 {
+                        let next = (i + di, j + dj);
+                        if (min_i..=max_i).contains(&next.0)
+                            && (min_j..=max_j).contains(&next.1)
+                            && !cells.contains(&next)
+                            && visited.insert(next)
+                        {
+                            queue.push_back(next);
+                        }
+                    }
                 }
             }
         }
-        area * perimeter
     }
+    // The padding forms the outside, which is not a hole
+    components - 1
 }
+
 fn main() {
     let path = Path::new("input.txt");
     let input = fs::read_to_string(path).expect("Cannot solve without input!");
@@ -129,32 +207,76 @@ fn main() {
     println!("Part 1: {}, {:?}", part_1(&graph), start_1.elapsed());
     let start_2 = Instant::now();
     println!("Part 2: {}, {:?}", part_2(&graph), start_2.elapsed());
-}
 
-fn part_1(graph: &Graph) -> u32 {
-    let mut cost = 0;
-    let mut visited: HashSet<(usize, usize)> = HashSet::new();
-    for i in 0..graph.height {
-        for j in 0..graph.width {
-            if visited.get(&(i, j)).is_none() {
-                cost += graph.plot_cost(i, j, &mut visited);
-            }
+    if env::args().any(|arg| arg == "--regions") {
+        for region in graph.regions() {
+            println!("\n{region}");
         }
     }
-    cost
+
+    if env::args().any(|arg| arg == "--render") {
+        let regions = graph.regions();
+        let status = format!(
+            "{} regions, fence price {}, bulk discount price {}",
+            regions.len(),
+            part_1(&graph),
+            part_2(&graph)
+        );
+        Renderer::new()
+            .draw(&canvas(&graph, &regions), &status)
+            .expect("Cannot write to stdout!");
+    }
 }
 
-fn part_2(graph: &Graph) -> u32 {
-    let mut cost = 0;
-    let mut visited: HashSet<(usize, usize)> = HashSet::new();
-    for i in 0..graph.height {
-        for j in 0..graph.width {
-            if visited.get(&(i, j)).is_none() {
-                cost += graph.plot_cost_2(i, j, &mut visited);
+/// The garden with every region coloured differently from its neighbours
+fn canvas(graph: &Graph, regions: &[Region]) -> Canvas {
+    let mut region_of = vec![vec![0; graph.width]; graph.height];
+    for (index, region) in regions.iter().enumerate() {
+        for &(i, j) in &region.cells {
+            region_of[i][j] = index;
+        }
+    }
+
+    // Greedy colouring, picking the first colour no coloured neighbour has
+    let mut colours: Vec<Option<usize>> = vec![None; regions.len()];
+    for (index, region) in regions.iter().enumerate() {
+        let mut taken = [false; Colour::PALETTE.len()];
+        for &(i, j) in &region.cells {
+            for (di, dj) in DIRECTIONS {
+                let (ni, nj) = (i as i32 + di, j as i32 + dj);
+                if ni < 0 || nj < 0 || ni >= graph.height as i32 || nj >= graph.width as i32 {
+                    continue;
+                }
+                if let Some(colour) = colours[region_of[ni as usize][nj as usize]] {
+                    taken[colour] = true;
+                }
             }
         }
+        colours[index] = Some(taken.iter().position(|&taken| !taken).unwrap_or(index));
+    }
+
+    let mut canvas = Canvas::new(graph.width, graph.height, Cell::EMPTY);
+    for (index, region) in regions.iter().enumerate() {
+        let colour = Colour::nth(colours[index].unwrap_or(index));
+        for &(i, j) in &region.cells {
+            canvas.set(j, i, Cell::new(region.label).fg(Colour::Black).bg(colour));
+        }
     }
-    cost
+    canvas
+}
+
+fn part_1(graph: &Graph) -> usize {
+    graph
+        .regions()
+        .iter()
+        .fold(0, |cost, region| cost + region.area * region.perimeter)
+}
+
+fn part_2(graph: &Graph) -> usize {
+    graph
+        .regions()
+        .iter()
+        .fold(0, |cost, region| cost + region.area * region.sides)
 }
 
 #[cfg(test)]
@@ -201,39 +323,54 @@ AAAAAA";
     }
 
     #[test]
-    fn test_plot_cost_2() {
+    fn test_regions() {
         let graph = Graph::from_str(G1);
-        let mut visited = HashSet::new();
-        assert_eq!(graph.plot_cost_2(0, 0, &mut visited), 16);
-        assert_eq!(graph.plot_cost_2(1, 0, &mut visited), 16);
-        assert_eq!(graph.plot_cost_2(3, 0, &mut visited), 12);
-        assert_eq!(graph.plot_cost_2(1, 2, &mut visited), 32);
-        assert_eq!(graph.plot_cost_2(1, 3, &mut visited), 4);
+        let costs: Vec<(char, usize)> = graph
+            .regions()
+            .iter()
+            .map(|region| (region.label, region.area * region.sides))
+            .collect();
+        assert_eq!(
+            costs,
+            vec![('A', 16), ('B', 16), ('C', 32), ('D', 4), ('E', 12)]
+        );
+
+        let regions = Graph::from_str(G2).regions();
+        assert_eq!(regions[0].label, 'O');
+        assert_eq!(regions[0].holes, 4);
+        assert_eq!(regions[0].sides, 20);
+        assert_eq!(regions[1].cells, vec![(1, 1)]);
+        assert_eq!(regions[1].holes, 0);
     }
 
     #[test]
-    fn test_plot_fences() {
+    fn test_region_display() {
         let graph = Graph::from_str(G1);
         assert_eq!(
-            graph.fences(0, 0),
-            HashSet::from([(-1, 0), (0, -1), (1, 0)])
+            graph.regions()[2].to_string(),
+            "C: area 4, perimeter 10, sides 8, holes 0\nC.\nCC\n.C\n"
         );
-        assert_eq!(graph.fences(0, 1), HashSet::from([(-1, 0), (1, 0)]));
-        assert_eq!(graph.fences(0, 2), HashSet::from([(-1, 0), (1, 0)]));
-        assert_eq!(graph.fences(0, 3), HashSet::from([(-1, 0), (0, 1), (1, 0)]));
     }
 
     #[test]
-    fn test_fence_cost() {
+    fn test_canvas() {
         let graph = Graph::from_str(G1);
-        let mut visited = HashSet::new();
-        assert_eq!(graph.fence_cost(0, 0, &mut visited), 3);
-        visited.insert((0, 0));
-        assert_eq!(graph.fence_cost(0, 1, &mut visited), 0);
-        visited.insert((0, 1));
-        assert_eq!(graph.fence_cost(0, 2, &mut visited), 0);
-        visited.insert((0, 2));
-        assert_eq!(graph.fence_cost(0, 3, &mut visited), 1);
+        let regions = graph.regions();
+        let canvas = canvas(&graph, &regions);
+        // Neighbouring regions never share a colour
+        let (b, c) = (canvas.get(1, 1).unwrap(), canvas.get(2, 1).unwrap());
+        assert_eq!((b.symbol, c.symbol), ('B', 'C'));
+        assert_ne!(b.background, c.background);
+        assert_eq!(canvas.get(0, 0).unwrap().background, Some(Colour::nth(0)));
+    }
+
+    #[test]
+    fn test_holes() {
+        // Diagonal gaps do not enclose anything
+        let graph = Graph::from_str("AAA\nABA\nAAB");
+        assert_eq!(graph.regions()[0].holes, 0);
+        let graph = Graph::from_str("AAAA\nABBA\nAAAA");
+        assert_eq!(graph.regions()[0].holes, 1);
     }
 
     #[test]
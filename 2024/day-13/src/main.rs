@@ -1,44 +1,116 @@
-use std::{fs, path::Path, time::Instant};
+use std::{env, fmt, fs, path::Path, time::Instant};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 fn main() {
     let path = Path::new("input.txt");
     let input: Vec<Machine> = fs::read_to_string(path)
         .expect("Cannot solve without input!")
         .split("\n\n")
-        .map(|machine| Machine::new(machine))
+        .map(Machine::new)
         .collect();
+    threads::configure(&env::args().collect::<Vec<_>>());
 
     let start_1 = Instant::now();
     println!("Part 1: {}, {:?}", part_1(&input), start_1.elapsed());
     let start_2 = Instant::now();
     println!("Part 2: {}, {:?}", part_2(&input), start_2.elapsed());
-}
 
-fn part_1(input: &[Machine]) -> i64 {
-    let mut sum: i64 = 0;
-    for machine in input {
-        if let Some(solution) = machine.solve(0) {
-            sum += solution;
-        }
+    if env::args().any(|arg| arg == "--report") {
+        print!("\n{}", report(&input));
     }
-    sum
 }
 
-fn part_2(input: &[Machine]) -> i64 {
-    let mut sum: i64 = 0;
-    for machine in input {
-        if let Some(solution) = machine.solve(10000000000000) {
-            sum += solution;
+fn part_1(input: &[Machine]) -> i128 {
+    total_cost(input, &PART_1)
+}
+
+fn part_2(input: &[Machine]) -> i128 {
+    total_cost(input, &PART_2)
+}
+
+fn total_cost(input: &[Machine], settings: &Settings) -> i128 {
+    #[cfg(not(feature = "parallel"))]
+    let machines = input.iter();
+    #[cfg(feature = "parallel")]
+    let machines = input.par_iter();
+    machines
+        .filter_map(|machine| machine.solve(settings).ok())
+        .map(|presses| presses.cost)
+        .sum()
+}
+
+/// Token cost of a single press of each button.
+#[derive(Debug, Clone, Copy)]
+struct Costs {
+    a: i128,
+    b: i128,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    /// Added to both coordinates of every prize
+    prize_offset: i128,
+    /// Maximum number of presses per button
+    press_limit: Option<i128>,
+    costs: Costs,
+}
+
+const COSTS: Costs = Costs { a: 3, b: 1 };
+
+const PART_1: Settings = Settings {
+    prize_offset: 0,
+    press_limit: Some(100),
+    costs: COSTS,
+};
+
+const PART_2: Settings = Settings {
+    prize_offset: 10000000000000,
+    press_limit: None,
+    costs: COSTS,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Presses {
+    a: i128,
+    b: i128,
+    cost: i128,
+}
+
+/// Why a prize cannot be won
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unsolvable {
+    /// Reaching the prize takes a fractional number of presses
+    NonInteger,
+    /// Reaching the prize takes a negative number of presses
+    Negative,
+    /// Reaching the prize takes more presses than allowed
+    ExceedsLimit,
+    /// The buttons move in the same direction, and no combination of presses
+    /// along it reaches the prize within the limits
+    Collinear,
+    /// The numbers do not fit in an `i128`
+    Overflow,
+}
+
+impl fmt::Display for Unsolvable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unsolvable::NonInteger => write!(f, "non-integer number of presses"),
+            Unsolvable::Negative => write!(f, "negative number of presses"),
+            Unsolvable::ExceedsLimit => write!(f, "exceeds the press limit"),
+            Unsolvable::Collinear => write!(f, "collinear buttons miss the prize"),
+            Unsolvable::Overflow => write!(f, "overflow"),
         }
     }
-    sum
 }
 
 #[derive(Debug)]
 struct Machine {
-    button_a: (i64, i64),
-    button_b: (i64, i64),
-    prize: (i64, i64),
+    button_a: (i128, i128),
+    button_b: (i128, i128),
+    prize: (i128, i128),
 }
 
 impl Machine {
@@ -48,7 +120,7 @@ impl Machine {
                 .nth(1)
                 .unwrap()
                 .split(", ")
-                .map(|part| part[2..].parse::<i64>().unwrap())
+                .map(|part| part[2..].parse::<i128>().unwrap())
         });
         let mut button_a_values = values.next().unwrap();
         let mut button_b_values = values.next().unwrap();
@@ -66,43 +138,179 @@ impl Machine {
         }
     }
 
-    fn solve(&self, prize_modifier: i64) -> Option<i64> {
-        // equation format: eq.0 * a + eq.1 * b = eq.2
-        let mut eq_1 = [
-            self.button_a.0,
-            self.button_b.0,
-            self.prize.0 + prize_modifier,
-        ];
-        let mut eq_2 = [
-            self.button_a.1,
-            self.button_b.1,
-            self.prize.1 + prize_modifier,
-        ];
-
-        // Multiply so that a-term has same value
-        let eq_2_multiplier = eq_1[0];
-        let eq_1_multiplier = eq_2[0];
-        for item in &mut eq_1 {
-            *item *= eq_1_multiplier;
+    /// Cheapest way to win the prize, or why it cannot be won.
+    fn solve(&self, settings: &Settings) -> Result<Presses, Unsolvable> {
+        use Unsolvable::*;
+        let (ax, ay) = self.button_a;
+        let (bx, by) = self.button_b;
+        let px = self
+            .prize
+            .0
+            .checked_add(settings.prize_offset)
+            .ok_or(Overflow)?;
+        let py = self
+            .prize
+            .1
+            .checked_add(settings.prize_offset)
+            .ok_or(Overflow)?;
+
+        let determinant = cross((ax, ay), (bx, by)).ok_or(Overflow)?;
+        let (a, b) = if determinant == 0 {
+            // Buttons are parallel, the prize must be on the same line
+            if cross((ax, ay), (px, py)).ok_or(Overflow)? != 0
+                || cross((bx, by), (px, py)).ok_or(Overflow)? != 0
+            {
+                return Err(Collinear);
+            }
+            // Any non-zero axis describes the whole line
+            let (a, b, p) = if (ax, bx) != (0, 0) {
+                (ax, bx, px)
+            } else {
+                (ay, by, py)
+            };
+            cheapest_on_line(a, b, p, settings).ok_or(Collinear)?
+        } else {
+            // Cramer's rule
+            let a = cross((px, py), (bx, by)).ok_or(Overflow)?;
+            let b = cross((ax, ay), (px, py)).ok_or(Overflow)?;
+            if a % determinant != 0 || b % determinant != 0 {
+                return Err(NonInteger);
+            }
+            (a / determinant, b / determinant)
+        };
+
+        if a < 0 || b < 0 {
+            return Err(Negative);
         }
-        for item in &mut eq_2 {
-            *item *= eq_2_multiplier;
+        if settings
+            .press_limit
+            .is_some_and(|limit| a > limit || b > limit)
+        {
+            return Err(ExceedsLimit);
         }
+        let cost = a
+            .checked_mul(settings.costs.a)
+            .zip(b.checked_mul(settings.costs.b))
+            .and_then(|(a, b)| a.checked_add(b))
+            .ok_or(Overflow)?;
+        Ok(Presses { a, b, cost })
+    }
+}
 
-        // Substitute a to find b
-        let b_eq = (eq_1[1] - eq_2[1], eq_1[2] - eq_2[2]);
-        if b_eq.1 % b_eq.0 != 0 {
-            return None;
+/// Outcome of every machine with the settings of both parts.
+fn report(input: &[Machine]) -> String {
+    let mut output = String::new();
+    for (n, machine) in input.iter().enumerate() {
+        let ((ax, ay), (bx, by), (px, py)) = (machine.button_a, machine.button_b, machine.prize);
+        output.push_str(&format!(
+            "Machine {}: A X+{ax} Y+{ay}, B X+{bx} Y+{by}, prize X={px} Y={py}\n",
+            n + 1
+        ));
+        for (part, settings) in [(1, &PART_1), (2, &PART_2)] {
+            let outcome = match machine.solve(settings) {
+                Ok(Presses { a, b, cost }) => format!("A {a}, B {b}, {cost} tokens"),
+                Err(reason) => format!("no prize, {reason}"),
+            };
+            output.push_str(&format!("  part {part}: {outcome}\n"));
         }
-        let b = b_eq.1 / b_eq.0;
+    }
+    for (part, settings) in [(1, &PART_1), (2, &PART_2)] {
+        let won = input
+            .iter()
+            .filter(|machine| machine.solve(settings).is_ok())
+            .count();
+        output.push_str(&format!(
+            "Part {part}: {won} of {} prizes, {} tokens\n",
+            input.len(),
+            total_cost(input, settings)
+        ));
+    }
+    output
+}
+
+/// 2D cross product `u.x * v.y - u.y * v.x`.
+fn cross(u: (i128, i128), v: (i128, i128)) -> Option<i128> {
+    u.0.checked_mul(v.1)?.checked_sub(u.1.checked_mul(v.0)?)
+}
+
+/// Cheapest non-negative `(a, b)` with `x_a * a + x_b * b == p`, within the press limit.
+fn cheapest_on_line(x_a: i128, x_b: i128, p: i128, settings: &Settings) -> Option<(i128, i128)> {
+    if (x_a, x_b) == (0, 0) {
+        return (p == 0).then_some((0, 0));
+    }
+    let (g, s, t) = extended_gcd(x_a, x_b);
+    if p % g != 0 {
+        return None;
+    }
+    // All solutions: a = a_0 + step_a * k, b = b_0 - step_b * k
+    let (a_0, b_0) = (s.checked_mul(p / g)?, t.checked_mul(p / g)?);
+    let (step_a, step_b) = (x_b / g, -(x_a / g));
 
-        // Substitute b to find a
-        let a_eq = (eq_2[0], eq_2[2] - (b * eq_2[1]));
-        if a_eq.1 % a_eq.0 != 0 {
-            return None;
+    let mut range = (i128::MIN, i128::MAX);
+    for (start, step) in [(a_0, step_a), (b_0, step_b)] {
+        range = intersect(range, at_least(start, step, 0)?)?;
+        if let Some(limit) = settings.press_limit {
+            range = intersect(range, at_most(start, step, limit)?)?;
         }
-        let a = a_eq.1 / a_eq.0;
-        Some(a * 3 + b)
+    }
+
+    // Cost is linear in k, so the cheapest solution is at one end of the range
+    let slope = step_a
+        .checked_mul(settings.costs.a)?
+        .checked_add(step_b.checked_mul(settings.costs.b)?)?;
+    let k = match slope.signum() {
+        1 => range.0,
+        -1 => range.1,
+        _ => 0.clamp(range.0, range.1),
+    };
+    if k == i128::MIN || k == i128::MAX {
+        // Unbounded in the cheaper direction, only possible with negative costs
+        return None;
+    }
+    Some((
+        a_0.checked_add(step_a.checked_mul(k)?)?,
+        b_0.checked_add(step_b.checked_mul(k)?)?,
+    ))
+}
+
+/// Range of `k` for which `start + step * k >= min`, `None` if empty.
+fn at_least(start: i128, step: i128, min: i128) -> Option<(i128, i128)> {
+    match step.signum() {
+        1 => Some((div_ceil(min - start, step), i128::MAX)),
+        -1 => Some((i128::MIN, div_floor(start - min, -step))),
+        _ => (start >= min).then_some((i128::MIN, i128::MAX)),
+    }
+}
+
+/// Range of `k` for which `start + step * k <= max`, `None` if empty.
+fn at_most(start: i128, step: i128, max: i128) -> Option<(i128, i128)> {
+    match step.signum() {
+        1 => Some((i128::MIN, div_floor(max - start, step))),
+        -1 => Some((div_ceil(start - max, -step), i128::MAX)),
+        _ => (start <= max).then_some((i128::MIN, i128::MAX)),
+    }
+}
+
+fn intersect(a: (i128, i128), b: (i128, i128)) -> Option<(i128, i128)> {
+    let range = (a.0.max(b.0), a.1.min(b.1));
+    (range.0 <= range.1).then_some(range)
+}
+
+fn div_floor(a: i128, b: i128) -> i128 {
+    a.div_euclid(b)
+}
+
+fn div_ceil(a: i128, b: i128) -> i128 {
+    -(-a).div_euclid(b)
+}
+
+/// Returns `(g, s, t)` such that `a * s + b * t == g == gcd(a, b)` and `g > 0`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a.abs(), a.signum(), 0)
+    } else {
+        let (g, s, t) = extended_gcd(b, a % b);
+        (g, t, s - (a / b) * t)
     }
 }
 
@@ -117,25 +325,141 @@ mod tests {
 Button B: X+22, Y+67
 Prize: X=8400, Y=5400",
         );
-        assert_eq!(machine.solve(0), Some(280));
+        assert_eq!(machine.solve(&PART_1).map(|presses| presses.cost), Ok(280));
         let machine = Machine::new(
             "Button A: X+26, Y+66
 Button B: X+67, Y+21
 Prize: X=12748, Y=12176",
         );
-        assert_eq!(machine.solve(0), None);
+        assert_eq!(
+            machine.solve(&PART_1).map(|presses| presses.cost),
+            Err(Unsolvable::NonInteger)
+        );
         let machine = Machine::new(
             "Button A: X+17, Y+86
 Button B: X+84, Y+37
 Prize: X=7870, Y=6450",
         );
-        assert_eq!(machine.solve(0), Some(200));
+        assert_eq!(machine.solve(&PART_1).map(|presses| presses.cost), Ok(200));
         let machine = Machine::new(
             "Button A: X+69, Y+23
 Button B: X+27, Y+71
 Prize: X=18641, Y=10279",
         );
-        assert_eq!(machine.solve(0), None);
+        assert_eq!(
+            machine.solve(&PART_1).map(|presses| presses.cost),
+            Err(Unsolvable::NonInteger)
+        );
+    }
+
+    #[test]
+    fn test_press_limit() {
+        let machine = Machine::new(
+            "Button A: X+1, Y+2
+Button B: X+3, Y+1
+Prize: X=150, Y=250",
+        );
+        assert_eq!(machine.solve(&PART_1), Err(Unsolvable::ExceedsLimit));
+        let unlimited = Settings {
+            press_limit: None,
+            ..PART_1
+        };
+        assert_eq!(
+            machine.solve(&unlimited),
+            Ok(Presses {
+                a: 120,
+                b: 10,
+                cost: 370
+            })
+        );
+    }
+
+    #[test]
+    fn test_parallel_buttons() {
+        let machine = Machine::new(
+            "Button A: X+1, Y+1
+Button B: X+2, Y+2
+Prize: X=10, Y=10",
+        );
+        assert_eq!(
+            machine.solve(&PART_1),
+            Ok(Presses {
+                a: 0,
+                b: 5,
+                cost: 5
+            })
+        );
+        let expensive_b = Settings {
+            costs: Costs { a: 1, b: 5 },
+            ..PART_1
+        };
+        assert_eq!(
+            machine.solve(&expensive_b),
+            Ok(Presses {
+                a: 10,
+                b: 0,
+                cost: 10
+            })
+        );
+        let limited = Settings {
+            press_limit: Some(4),
+            costs: Costs { a: 1, b: 5 },
+            ..PART_1
+        };
+        assert_eq!(
+            machine.solve(&limited),
+            Ok(Presses {
+                a: 4,
+                b: 3,
+                cost: 19
+            })
+        );
+
+        let machine = Machine::new(
+            "Button A: X+2, Y+2
+Button B: X+4, Y+4
+Prize: X=3, Y=3",
+        );
+        assert_eq!(machine.solve(&PART_1), Err(Unsolvable::Collinear));
+        let machine = Machine::new(
+            "Button A: X+2, Y+2
+Button B: X+4, Y+4
+Prize: X=4, Y=6",
+        );
+        assert_eq!(machine.solve(&PART_1), Err(Unsolvable::Collinear));
+    }
+
+    #[test]
+    fn test_negative() {
+        let machine = Machine::new(
+            "Button A: X+1, Y+0
+Button B: X+0, Y+1
+Prize: X=-3, Y=5",
+        );
+        assert_eq!(machine.solve(&PART_1), Err(Unsolvable::Negative));
+    }
+
+    #[test]
+    fn test_report() {
+        let input: Vec<Machine> = [
+            "Button A: X+94, Y+34\nButton B: X+22, Y+67\nPrize: X=8400, Y=5400",
+            "Button A: X+26, Y+66\nButton B: X+67, Y+21\nPrize: X=12748, Y=12176",
+        ]
+        .map(Machine::new)
+        .into();
+        let report = report(&input);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(
+            lines[0],
+            "Machine 1: A X+94 Y+34, B X+22 Y+67, prize X=8400 Y=5400"
+        );
+        assert_eq!(lines[1], "  part 1: A 80, B 40, 280 tokens");
+        assert_eq!(
+            lines[2],
+            "  part 2: no prize, non-integer number of presses"
+        );
+        assert_eq!(lines[6], "Part 1: 1 of 2 prizes, 280 tokens");
+        assert_eq!(lines[7], "Part 2: 1 of 2 prizes, 459236326669 tokens");
     }
 
     #[test]
@@ -145,6 +469,6 @@ Prize: X=18641, Y=10279",
 Button B: X+2, Y+1
 Prize: X=8, Y=6",
         );
-        assert_eq!(machine.solve(0), Some(6));
+        assert_eq!(machine.solve(&PART_1).map(|presses| presses.cost), Ok(6));
     }
 }
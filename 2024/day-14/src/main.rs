@@ -1,49 +1,178 @@
 use std::{
     collections::{HashSet, VecDeque},
-    fs,
+    env, fs,
     io::{self, stdin, BufRead, Write},
     path::Path,
+    str::FromStr,
     time::Instant,
 };
 
+#[cfg(feature = "export")]
+use render::{Canvas, Cell, Colour, Exporter};
 use terminal_size::{terminal_size, Height};
 
 const CANDIDATE_MINIMUM_SIZE: u32 = 15;
+/// Frames exported by default, the simulation takes thousands of seconds
+#[cfg(feature = "export")]
+const EXPORT_FRAMES: usize = 200;
 
 fn main() {
     let path = Path::new("input.txt");
-    let input: Vec<Robot> = fs::read_to_string(path)
-        .expect("Cannot solve without input!")
-        .lines()
-        .map(|machine| Robot::new(machine, 101, 103))
-        .collect();
+    let input = parse(&fs::read_to_string(path).expect("Cannot solve without input!"));
+    let args: Vec<String> = env::args().collect();
+
+    // Room size from `--size WIDTHxHEIGHT`, or the smallest room fitting all robots
+    let room = match args.iter().position(|arg| arg == "--size") {
+        Some(index) => args
+            .get(index + 1)
+            .and_then(|size| size.parse().ok())
+            .expect("Size should be given as WIDTHxHEIGHT"),
+        None => Room::infer(&input),
+    };
 
     let start_1 = Instant::now();
     let part_1_result = format!(
         "Part 1: {}, {:?}\n",
-        part_1(&input, 101, 103),
+        part_1(&input, room),
         start_1.elapsed()
     );
-    part_2(&input, &part_1_result);
+
+    if args.iter().any(|arg| arg == "--interactive") {
+        view_candidates(&input, room, &part_1_result);
+    } else {
+        print!("{part_1_result}");
+        let start_2 = Instant::now();
+        match part_2(&input, room) {
+            Some(second) => println!("Part 2: {}, {:?}", second, start_2.elapsed()),
+            None => println!("Part 2: no tree found, {:?}", start_2.elapsed()),
+        }
+    }
+
+    // Seconds until the tree appears, or one full cycle without a tree, spread
+    // over at most `EXPORT_FRAMES` frames unless `--limit` says otherwise
+    #[cfg(feature = "export")]
+    if let Some(exporter) = Exporter::from_args(&args) {
+        let last =
+            part_2(&input, room).map_or(room.width * room.height, |second| second as i32 + 1);
+        let mut exporter = exporter.spread(last as usize, EXPORT_FRAMES);
+        simulate(&input, room, 0..last, |_, positions| {
+            exporter
+                .frame(|| canvas(positions, room))
+                .expect("Cannot write frame!");
+        });
+        let final_positions: Vec<_> = input
+            .iter()
+            .map(|robot| robot.position(last - 1, room))
+            .collect();
+        let frames = exporter
+            .finish_with(|| canvas(&final_positions, room))
+            .expect("Cannot write frames!");
+        println!("Exported {frames} frames");
+    }
+    #[cfg(not(feature = "export"))]
+    if args.iter().any(|arg| arg == "--export") {
+        eprintln!("Ignoring --export, built without the export feature");
+    }
 }
 
-fn part_1(input: &[Robot], width: i32, height: i32) -> i32 {
-    let positions = input
-        .iter()
-        .map(|robot| robot.position(100))
-        .collect::<HashSet<(i32, i32)>>();
-    let mut quadrants = [0, 0, 0, 0];
-    for position in positions {
-        if let Some(index) = quadrant(position.0, position.1, width, height) {
-            quadrants[index] += 1;
+/// Call `on_second` with the positions of the robots on each of the `seconds`
+#[cfg(any(feature = "export", test))]
+fn simulate(
+    input: &[Robot],
+    room: Room,
+    seconds: std::ops::Range<i32>,
+    mut on_second: impl FnMut(i32, &[(i32, i32)]),
+) {
+    let mut positions = Vec::with_capacity(input.len());
+    for second in seconds {
+        positions.clear();
+        positions.extend(input.iter().map(|robot| robot.position(second, room)));
+        on_second(second, &positions);
+    }
+}
+
+#[cfg(feature = "export")]
+fn canvas(positions: &[(i32, i32)], room: Room) -> Canvas {
+    let mut canvas = Canvas::new(room.width as usize, room.height as usize, Cell::EMPTY);
+    for &(x, y) in positions {
+        canvas.set(
+            x as usize,
+            y as usize,
+            Cell::new('#').fg(Colour::BrightGreen),
+        );
+    }
+    canvas
+}
+
+fn part_1(input: &[Robot], room: Room) -> i32 {
+    safety_factor(input, room, 100, (2, 2))
+}
+
+/// Product of robot counts in each sub-region after `seconds`, with the room
+/// split into `columns` x `rows` sub-regions.
+fn safety_factor(input: &[Robot], room: Room, seconds: i32, (columns, rows): (i32, i32)) -> i32 {
+    let mut counts = vec![0; (columns * rows) as usize];
+    for (x, y) in input.iter().map(|robot| robot.position(seconds, room)) {
+        if let (Some(column), Some(row)) =
+            (part(x, room.width, columns), part(y, room.height, rows))
+        {
+            counts[(row * columns + column) as usize] += 1;
         }
     }
-    quadrants.iter().product()
+    counts.iter().product()
+}
+
+/// Find the second when the robots form the Christmas tree.
+///
+/// The robots are the most tightly packed when they form the picture. Their x
+/// coordinates repeat every `room.width` seconds and y coordinates every
+/// `room.height` seconds, so the axes are optimized separately and combined with the CRT.
+fn part_2(input: &[Robot], room: Room) -> Option<i64> {
+    let best_x = (0..room.width)
+        .min_by_key(|&second| variance(input.iter().map(|robot| robot.position(second, room).0)))?;
+    let best_y = (0..room.height)
+        .min_by_key(|&second| variance(input.iter().map(|robot| robot.position(second, room).1)))?;
+    crt(
+        (best_x as i64, room.width as i64),
+        (best_y as i64, room.height as i64),
+    )
+}
+
+/// Variance of the values, scaled by the number of values squared to stay exact.
+fn variance(values: impl Iterator<Item = i32>) -> i64 {
+    let (mut count, mut sum, mut sum_of_squares) = (0_i64, 0_i64, 0_i64);
+    for value in values {
+        count += 1;
+        sum += value as i64;
+        sum_of_squares += (value as i64).pow(2);
+    }
+    count * sum_of_squares - sum * sum
+}
+
+/// Smallest non-negative `t` with `t ≡ a (mod n)` and `t ≡ b (mod m)`.
+fn crt((a, n): (i64, i64), (b, m): (i64, i64)) -> Option<i64> {
+    let (g, p, _) = extended_gcd(n, m);
+    if (b - a) % g != 0 {
+        return None;
+    }
+    let lcm = n / g * m;
+    let k = ((b - a) / g * p).rem_euclid(m / g);
+    Some((a + n * k).rem_euclid(lcm))
+}
+
+/// Returns `(g, s, t)` such that `a * s + b * t == g == gcd(a, b)`.
+fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, s, t) = extended_gcd(b, a % b);
+        (g, t, s - (a / b) * t)
+    }
 }
 
 /// Render candidates to terminal, Enter gives you the next candidate, Ctrl-C stops the program
-fn part_2(input: &[Robot], part_1_result: &str) {
-    let mut grid = [['.'; 101]; 103];
+fn view_candidates(input: &[Robot], room: Room, part_1_result: &str) {
+    let mut grid = vec![vec!['.'; room.width as usize]; room.height as usize];
     let mut second = 0;
     let mut stdout = io::stdout().lock();
     let mut start = Instant::now();
@@ -60,7 +189,7 @@ fn part_2(input: &[Robot], part_1_result: &str) {
     loop {
         let positions = input
             .iter()
-            .map(|robot| robot.position(second))
+            .map(|robot| robot.position(second, room))
             .collect::<HashSet<(i32, i32)>>();
 
         // Only render potential results
@@ -93,8 +222,9 @@ fn part_2(input: &[Robot], part_1_result: &str) {
         stdout
             .write_all(
                 format!(
-                    "Terminal height: {}, 106 lines rendered, second {}, {:?}\n",
+                    "Terminal height: {}, {} lines rendered, second {}, {:?}\n",
                     terminal_height(),
+                    room.height + 3,
                     second,
                     start.elapsed(),
                 )
@@ -116,10 +246,10 @@ fn largest_robot_cluster(positions: &HashSet<(i32, i32)>) -> u32 {
     let mut visited: HashSet<(i32, i32)> = HashSet::new();
     let mut highest: u32 = 0;
     for position in positions {
-        if visited.get(&position).is_some() {
+        if visited.contains(position) {
             continue;
         }
-        let size = graph_size(*position, &positions, &mut visited);
+        let size = graph_size(*position, positions, &mut visited);
         if size > CANDIDATE_MINIMUM_SIZE {
             return size;
         }
@@ -147,7 +277,7 @@ fn graph_size(
         }
         for dir in DIRECTIONS {
             let new_pos = (node.0 + dir.0, node.1 + dir.1);
-            if nodes.get(&new_pos).is_some() && visited.get(&new_pos).is_none() {
+            if nodes.contains(&new_pos) && !visited.contains(&new_pos) {
                 queue.push_back(new_pos);
                 visited.insert(new_pos);
             }
@@ -160,31 +290,65 @@ fn terminal_height() -> u16 {
     terminal_size().map(|(_, Height(h))| h).unwrap()
 }
 
-/// Get quadrant index for a position in <width>x<height> space.
-/// TL = 0, TR = 1, BL = 2, BR = 3
-/// If position is exactly on the middle line, return None.
-fn quadrant(x: i32, y: i32, width: i32, height: i32) -> Option<usize> {
-    let middle_w = width / 2;
-    let middle_h = height / 2;
-    if x == middle_w || y == middle_h {
+/// Index of the part containing `coordinate`, when `length` is split into `parts`.
+/// If the parts are separated by lines, like the middle line of a room split in
+/// two, coordinates on the lines return None.
+fn part(coordinate: i32, length: i32, parts: i32) -> Option<i32> {
+    if (coordinate + 1) * parts % (length + 1) == 0 {
         None
     } else {
-        let quadrant = y / (middle_h + 1) * 2 + x / (middle_w + 1);
-        Some(quadrant as usize)
+        Some((coordinate + 1) * parts / (length + 1))
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Room {
+    width: i32,
+    height: i32,
+}
+
+impl Room {
+    /// Smallest room containing the starting positions of all robots
+    fn infer(robots: &[Robot]) -> Self {
+        Self {
+            width: robots.iter().map(|robot| robot.x + 1).max().unwrap_or(1),
+            height: robots.iter().map(|robot| robot.y + 1).max().unwrap_or(1),
+        }
+    }
+}
+
+impl FromStr for Room {
+    type Err = String;
+
+    /// Parse `WIDTHxHEIGHT`, e.g. `101x103`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s
+            .split_once('x')
+            .ok_or_else(|| format!("Invalid room size: {s}"))?;
+        let parse = |value: &str| match value.parse() {
+            Ok(value) if value > 0 => Ok(value),
+            _ => Err(format!("Invalid room size: {s}")),
+        };
+        Ok(Self {
+            width: parse(width)?,
+            height: parse(height)?,
+        })
+    }
+}
+
+fn parse(input: &str) -> Vec<Robot> {
+    input.lines().map(Robot::new).collect()
+}
+
 struct Robot {
     x: i32,
     y: i32,
     vx: i32,
     vy: i32,
-    width: i32,
-    height: i32,
 }
 
 impl Robot {
-    fn new(input: &str, width: i32, height: i32) -> Self {
+    fn new(input: &str) -> Self {
         let parsed: Vec<Vec<i32>> = input
             .split_whitespace()
             .map(|part| {
@@ -199,17 +363,15 @@ impl Robot {
             y: parsed[0][1],
             vx: parsed[1][0],
             vy: parsed[1][1],
-            width,
-            height,
         }
     }
 
-    fn position(&self, seconds: i32) -> (i32, i32) {
+    fn position(&self, seconds: i32, room: Room) -> (i32, i32) {
         let x = (self.x + seconds * self.vx)
-            .checked_rem_euclid(self.width)
+            .checked_rem_euclid(room.width)
             .unwrap();
         let y = (self.y + seconds * self.vy)
-            .checked_rem_euclid(self.height)
+            .checked_rem_euclid(room.height)
             .unwrap();
         (x, y)
     }
@@ -221,13 +383,16 @@ mod tests {
 
     #[test]
     fn test_robot() {
-        let robot = Robot::new("p=2,4 v=2,-3", 11, 7);
-        assert_eq!(robot.position(5), (1, 3));
+        let robot = Robot::new("p=2,4 v=2,-3");
+        assert_eq!(robot.position(5, ROOM), (1, 3));
     }
 
-    #[test]
-    fn test_part_1() {
-        let input: Vec<Robot> = "p=0,4 v=3,-3
+    const ROOM: Room = Room {
+        width: 11,
+        height: 7,
+    };
+
+    const INPUT: &str = "p=0,4 v=3,-3
 p=6,3 v=-1,-3
 p=10,3 v=-1,2
 p=2,0 v=2,-1
@@ -238,10 +403,82 @@ p=3,0 v=-1,-2
 p=9,3 v=2,3
 p=7,3 v=-1,2
 p=2,4 v=2,-3
-p=9,5 v=-3,-3"
-            .lines()
-            .map(|machine| Robot::new(machine, 11, 7))
+p=9,5 v=-3,-3";
+
+    #[test]
+    fn test_part_1() {
+        let input = parse(INPUT);
+        assert_eq!(Room::infer(&input), ROOM);
+        assert_eq!(part_1(&input, ROOM), 12);
+    }
+
+    #[test]
+    fn test_safety_factor() {
+        let input = parse(INPUT);
+        assert_eq!(safety_factor(&input, ROOM, 100, (1, 1)), 12);
+        // Columns 3 and 7 separate the parts, leaving 4, 6 and 1 robots
+        assert_eq!(safety_factor(&input, ROOM, 100, (3, 1)), 4 * 6);
+    }
+
+    #[test]
+    fn test_part() {
+        assert_eq!(part(49, 101, 2), Some(0));
+        assert_eq!(part(50, 101, 2), None);
+        assert_eq!(part(51, 101, 2), Some(1));
+        assert_eq!(part(33, 101, 3), None);
+        assert_eq!(part(100, 101, 3), Some(2));
+        assert_eq!(part(5, 10, 2), Some(1));
+    }
+
+    #[test]
+    fn test_room() {
+        assert_eq!(
+            "101x103".parse(),
+            Ok(Room {
+                width: 101,
+                height: 103
+            })
+        );
+        assert!("101".parse::<Room>().is_err());
+        assert!("0x5".parse::<Room>().is_err());
+    }
+
+    #[test]
+    fn test_part_2() {
+        // All robots meet at (5, 3) on second 30
+        let input: Vec<Robot> = (1..=10_i32)
+            .map(|k| {
+                let (vx, vy) = (k, 2 * k + 1);
+                let x = (5 - 30 * vx).rem_euclid(11);
+                let y = (3 - 30 * vy).rem_euclid(7);
+                Robot::new(&format!("p={x},{y} v={vx},{vy}"))
+            })
             .collect();
-        assert_eq!(part_1(&input, 11, 7), 12);
+        assert_eq!(input[0].position(30, ROOM), (5, 3));
+        assert_eq!(part_2(&input, ROOM), Some(30));
+    }
+
+    #[test]
+    fn test_simulate() {
+        let input = parse(INPUT);
+        let mut seconds = Vec::new();
+        simulate(&input, ROOM, 99..101, |second, positions| {
+            assert_eq!(positions.len(), input.len());
+            seconds.push(second);
+        });
+        assert_eq!(seconds, vec![99, 100]);
+
+        let robot = Robot::new("p=2,4 v=2,-3");
+        simulate(&[robot], ROOM, 5..6, |_, positions| {
+            assert_eq!(positions, [(1, 3)]);
+        });
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt((8, 11), (2, 7)), Some(30));
+        assert_eq!(crt((0, 101), (0, 103)), Some(0));
+        assert_eq!(crt((1, 4), (2, 6)), None);
+        assert_eq!(crt((2, 4), (4, 6)), Some(10));
     }
 }
@@ -1,239 +1,204 @@
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    fs,
+    env, fs,
+    io::{self, BufRead},
     path::Path,
     time::Instant,
 };
 
-use glam::IVec2;
+mod warehouse;
+
+#[cfg(feature = "export")]
+use render::Exporter;
+use render::{Canvas, Cell, Colour, Debugger, Renderer, Simulation};
+use warehouse::Warehouse;
 
 fn main() {
     let path = Path::new("input.txt");
     let input = fs::read_to_string(path).expect("Cannot solve without input!");
+    let args: Vec<String> = env::args().collect();
 
     let start_1 = Instant::now();
     println!("Part 1: {}, {:?}", part_1(&input), start_1.elapsed());
     let start_2 = Instant::now();
     println!("Part 2: {}, {:?}", part_2(&input), start_2.elapsed());
-}
 
-fn part_1(input: &str) -> i32 {
-    let (mut robot, moves) = parse(input, false);
-    for step in moves.chars() {
-        robot.try_move(step);
+    if args.iter().any(|arg| arg == "--render") {
+        for scale in [1, 2] {
+            let (mut warehouse, moves) = parse(&input, scale);
+            warehouse.run(&[moves]);
+            let status = format!("Scale {scale}, GPS sum {}", warehouse.coordinate_sum());
+            print!("{}", renderer().render(&canvas(&warehouse), &status));
+        }
     }
-    robot
-        .boxes
-        .iter()
-        .map(|(position, _)| 100 * position.y + position.x)
-        .sum()
-}
-
-fn part_2(input: &str) -> i32 {
-    let (mut robot, moves) = parse(input, true);
 
-    for step in moves.chars() {
-        robot.try_move(step);
+    #[cfg(feature = "export")]
+    if let Some(mut exporter) = Exporter::from_args(&args) {
+        let (mut warehouse, moves) = parse(&input, 2);
+        warehouse.run_with(&[moves], |warehouse| {
+            exporter
+                .frame(|| canvas(warehouse))
+                .expect("Cannot write frame!");
+        });
+        let frames = exporter
+            .finish_with(|| canvas(&warehouse))
+            .expect("Cannot write frames!");
+        println!("Exported {frames} frames");
+    }
+    #[cfg(not(feature = "export"))]
+    if args.iter().any(|arg| arg == "--export") {
+        eprintln!("Ignoring --export, built without the export feature");
     }
-    robot.coordinate_sum()
-}
 
-fn parse(input: &str, double_width: bool) -> (Robot, &str) {
-    let mut parts = input.split("\n\n");
-    (
-        Robot::new(parts.next().unwrap(), double_width),
-        parts.next().unwrap(),
-    )
-}
+    if args.iter().any(|arg| arg == "--debug") {
+        let (warehouse, moves) = parse(&input, 2);
+        Debugger::new(Replay::new(warehouse, moves))
+            .run(&renderer())
+            .expect("Cannot write to stdout!");
+    }
 
-struct Robot {
-    position: IVec2,
-    walls: HashMap<IVec2, IVec2>,
-    boxes: HashMap<IVec2, IVec2>,
-    double_width: bool,
+    if args.iter().any(|arg| arg == "--explore") {
+        let (warehouse, _) = parse(&input, 2);
+        explore(warehouse);
+    }
 }
 
-impl Robot {
-    fn new(grid: &str, double_width: bool) -> Self {
-        let mut position = IVec2::ZERO;
-        let mut walls = HashMap::new();
-        let mut boxes = HashMap::new();
-        for (y, row) in grid.lines().enumerate() {
-            for (x, symbol) in row.chars().enumerate() {
-                match symbol {
-                    '#' => {
-                        if double_width {
-                            let _ = walls.insert(
-                                IVec2::new((x as i32) * 2, y as i32),
-                                IVec2::new((x as i32) * 2 + 1, y as i32),
-                            );
-                            let _ = walls.insert(
-                                IVec2::new((x as i32) * 2 + 1, y as i32),
-                                IVec2::new((x as i32) * 2, y as i32),
-                            );
-                        } else {
-                            let _ = walls.insert(
-                                IVec2::new(x as i32, y as i32),
-                                IVec2::new(x as i32, y as i32),
-                            );
-                        }
-                    }
-                    '@' => {
-                        if double_width {
-                            position = IVec2::new((x as i32) * 2, y as i32);
-                        } else {
-                            position = IVec2::new(x as i32, y as i32);
-                        }
-                    }
-                    'O' => {
-                        if double_width {
-                            let _ = boxes.insert(
-                                IVec2::new((x as i32) * 2, y as i32),
-                                IVec2::new((x as i32) * 2 + 1, y as i32),
-                            );
-                            let _ = boxes.insert(
-                                IVec2::new((x as i32) * 2 + 1, y as i32),
-                                IVec2::new((x as i32) * 2, y as i32),
-                            );
-                        } else {
-                            let _ = boxes.insert(
-                                IVec2::new(x as i32, y as i32),
-                                IVec2::new(x as i32, y as i32),
-                            );
-                        }
+/// Read moves from stdin and print the warehouse after each line.
+/// `u` undoes and `r` redoes a move, a digit selects which robot moves.
+fn explore(mut warehouse: Warehouse) {
+    let mut robot = 0;
+    println!("{warehouse}");
+    for line in io::stdin().lock().lines() {
+        for command in line.expect("Cannot read stdin!").chars() {
+            match command {
+                'u' => {
+                    warehouse.undo();
+                }
+                'r' => {
+                    warehouse.redo();
+                }
+                digit if digit.is_ascii_digit() => {
+                    let index = digit.to_digit(10).unwrap() as usize;
+                    if index < warehouse.robots.len() {
+                        robot = index;
                     }
-                    _ => {}
+                }
+                direction => {
+                    warehouse.step(robot, direction);
                 }
             }
         }
+        println!("{warehouse}");
+    }
+}
+
+/// The moves of the first robot, played one at a time.
+struct Replay {
+    warehouse: Warehouse,
+    moves: Vec<char>,
+    /// Whether each move played so far changed anything, i.e. needs undoing
+    played: Vec<bool>,
+}
+
+impl Replay {
+    fn new(warehouse: Warehouse, moves: &str) -> Self {
         Self {
-            position,
-            walls,
-            boxes,
-            double_width,
+            warehouse,
+            moves: moves.chars().filter(|c| "^>v<".contains(*c)).collect(),
+            played: Vec::new(),
         }
     }
+}
 
-    /// Try to move, no-op if not possible
-    fn try_move(&mut self, direction: char) {
-        let dir_vec = match direction {
-            '^' => IVec2::NEG_Y,
-            '>' => IVec2::X,
-            'v' => IVec2::Y,
-            '<' => IVec2::NEG_X,
-            _ => return,
+impl Simulation for Replay {
+    fn forward(&mut self) -> bool {
+        let Some(&direction) = self.moves.get(self.played.len()) else {
+            return false;
         };
-        let new_position = self.position + dir_vec;
-        if self.walls.contains_key(&new_position) {
-            return;
-        } else if !self.boxes.contains_key(&new_position) {
-            self.position = new_position;
-            return;
-        }
-        if self.double_width {
-            self.try_move_2(dir_vec);
-        } else {
-            self.try_move_1(dir_vec);
-        }
+        let moved = self.warehouse.step(0, direction).is_some();
+        self.played.push(moved);
+        true
     }
 
-    fn try_move_1(&mut self, dir_vec: IVec2) {
-        let new_position = self.position + dir_vec;
-
-        let mut check_position = new_position + dir_vec;
-        while self.boxes.contains_key(&check_position) {
-            check_position += dir_vec;
+    fn back(&mut self) -> bool {
+        match self.played.pop() {
+            Some(moved) => {
+                if moved {
+                    self.warehouse.undo();
+                }
+                true
+            }
+            None => false,
         }
+    }
 
-        if !self.walls.contains_key(&check_position) {
-            self.position = new_position;
-            self.boxes.remove(&new_position);
-            self.boxes.insert(check_position, check_position);
-        }
+    fn canvas(&self) -> Canvas {
+        canvas(&self.warehouse)
     }
 
-    fn try_move_2(&mut self, dir_vec: IVec2) {
-        let new_position = self.position + dir_vec;
-        if !self.boxes.contains_key(&new_position) {
-            return;
-        }
-        let mut queue: VecDeque<IVec2> = VecDeque::from([new_position]);
-        let mut to_move = HashSet::new();
-        let mut movable = true;
-
-        while !queue.is_empty() {
-            let current = queue.pop_front().unwrap();
-            let neighbour = *self.boxes.get(&current).unwrap();
-
-            if self.walls.get(&(current + dir_vec)).is_some()
-                || self.walls.get(&(neighbour + dir_vec)).is_some()
-            {
-                movable = false;
-                break;
-            }
+    fn status(&self) -> String {
+        let robot = self.warehouse.robots[0];
+        let latest = match self.played.last() {
+            Some(true) => format!("{} moved", self.moves[self.played.len() - 1]),
+            Some(false) => format!("{} blocked", self.moves[self.played.len() - 1]),
+            None => "start".to_string(),
+        };
+        let remaining = &self.moves[self.played.len()..];
+        let next = match remaining.first() {
+            Some(next) => format!("next {next} ({} left)", remaining.len()),
+            None => "no moves left".to_string(),
+        };
+        format!("{latest}, robot at ({}, {}), {next}", robot.x, robot.y)
+    }
+}
 
-            to_move.insert(sorted(current, neighbour));
+fn renderer() -> Renderer {
+    Renderer::new()
+        .legend(style('@'), "robot")
+        .legend(style('O'), "box")
+        .legend(style('#'), "wall")
+}
 
-            if let Some(&current_push) = self.boxes.get(&(current + dir_vec)) {
-                let other = self.boxes.get(&current_push).unwrap();
-                let (first, second) = sorted(current_push, *other);
-                if !to_move.contains(&(first, second)) {
-                    to_move.insert(sorted(current_push, *other));
-                    queue.push_back(current_push);
-                }
-            };
-            if let Some(&current_push) = self.boxes.get(&(neighbour + dir_vec)) {
-                let other = self.boxes.get(&current_push).unwrap();
-                let (first, second) = sorted(current_push, *other);
-                if !to_move.contains(&(first, second)) {
-                    to_move.insert(sorted(current_push, *other));
-                    queue.push_back(current_push);
-                }
-            };
-        }
+/// Colour of each symbol of the warehouse map
+fn style(symbol: char) -> Cell {
+    match symbol {
+        '@' => Cell::new(symbol).fg(Colour::BrightRed),
+        'O' | '[' | ']' | '=' => Cell::new(symbol).fg(Colour::Yellow),
+        '#' => Cell::new(symbol).fg(Colour::Grey),
+        _ => Cell::new(symbol),
+    }
+}
 
-        if !movable {
-            return;
-        }
+fn canvas(warehouse: &Warehouse) -> Canvas {
+    Canvas::from_text(&warehouse.to_string(), style)
+}
 
-        // Movement possible, execute!
-        self.position = new_position;
-        for &(left, right) in to_move.iter() {
-            self.boxes.remove(&left);
-            self.boxes.remove(&right);
-        }
-        for &(left, right) in to_move.iter() {
-            let new_left = left + dir_vec;
-            let new_right = right + dir_vec;
-            self.boxes.insert(new_left, new_right);
-            self.boxes.insert(new_right, new_left);
-        }
-    }
+fn part_1(input: &str) -> i32 {
+    simulate(input, 1)
+}
 
-    fn coordinate_sum(&self) -> i32 {
-        let mut coordinates = HashSet::new();
-        for (&key, &value) in &self.boxes {
-            let (left, _) = sorted(key, value);
-            coordinates.insert(left);
-        }
-        coordinates
-            .iter()
-            .map(|position| 100 * position.y + position.x)
-            .sum()
-    }
+fn part_2(input: &str) -> i32 {
+    simulate(input, 2)
 }
 
-fn sorted(a: IVec2, b: IVec2) -> (IVec2, IVec2) {
-    if a.x < b.x || a.y < b.y {
-        (a, b)
-    } else {
-        (b, a)
-    }
+/// Run all moves with boxes `scale` cells wide, and return the coordinate sum
+fn simulate(input: &str, scale: i32) -> i32 {
+    let (mut warehouse, moves) = parse(input, scale);
+    warehouse.run(&[moves]);
+    warehouse.coordinate_sum()
+}
+
+fn parse(input: &str, scale: i32) -> (Warehouse, &str) {
+    let mut parts = input.split("\n\n");
+    (
+        Warehouse::new(parts.next().unwrap(), scale),
+        parts.next().unwrap(),
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use glam::IVec2;
 
     const INPUT1: &str = "########
 #..O.O.#
@@ -289,74 +254,142 @@ v^^>>><<^^<>>^v^<v^vv<>v^<<>^<^v^v><^<<<><<^<v><v<>vv>>v><v^<vv<>v^<<^";
         assert_eq!(part_2(INPUT2), 9021);
     }
 
+    /// Left edges of all boxes, sorted
+    fn boxes(warehouse: &Warehouse) -> Vec<IVec2> {
+        let mut boxes: Vec<IVec2> = warehouse.boxes.iter().map(|item| item.position).collect();
+        boxes.sort_by_key(|position| (position.y, position.x));
+        boxes
+    }
+
     #[test]
     fn test_to_move_2() {
-        let mut robot = Robot::new(INPUT3, true);
-        robot.try_move('<');
+        let (mut warehouse, _) = parse(INPUT3, 2);
+        warehouse.step(0, '<');
+        let initial = vec![IVec2::new(5, 3), IVec2::new(7, 3), IVec2::new(6, 4)];
+        assert_eq!(boxes(&warehouse), initial);
+        warehouse.step(0, 'v');
+        warehouse.step(0, 'v');
+        warehouse.step(0, '<');
+        warehouse.step(0, '<');
+        assert_eq!(boxes(&warehouse), initial);
+        warehouse.step(0, '^');
+        let pushed = vec![IVec2::new(5, 2), IVec2::new(7, 2), IVec2::new(6, 3)];
+        assert_eq!(boxes(&warehouse), pushed);
+        warehouse.step(0, '^');
+        assert_eq!(boxes(&warehouse), pushed);
+        warehouse.step(0, '<');
+        warehouse.step(0, '<');
+        warehouse.step(0, '^');
+        warehouse.step(0, '^');
+        assert_eq!(
+            boxes(&warehouse),
+            vec![IVec2::new(5, 1), IVec2::new(7, 2), IVec2::new(6, 3)]
+        );
+    }
+
+    #[test]
+    fn test_step() {
+        let (mut warehouse, _) = parse(INPUT3, 2);
+        let step = warehouse.step(0, '<').unwrap();
+        assert_eq!(step.from, IVec2::new(10, 3));
+        assert_eq!(step.pushed.len(), 2);
+        assert_eq!(warehouse.robots[0], IVec2::new(9, 3));
+        let step = warehouse.step(0, 'v').unwrap();
+        assert!(step.pushed.is_empty());
+        assert_eq!(warehouse.step(0, '?'), None);
+    }
+
+    #[test]
+    fn test_wide_boxes() {
+        let mut warehouse = Warehouse::new("#####\n#.O@#\n#...#\n#####", 3);
         assert_eq!(
-            robot.boxes,
-            HashMap::from([
-                (IVec2::new(5, 3), IVec2::new(6, 3)),
-                (IVec2::new(6, 3), IVec2::new(5, 3)),
-                (IVec2::new(7, 3), IVec2::new(8, 3)),
-                (IVec2::new(8, 3), IVec2::new(7, 3)),
-                (IVec2::new(6, 4), IVec2::new(7, 4)),
-                (IVec2::new(7, 4), IVec2::new(6, 4)),
-            ])
+            warehouse.to_string(),
+            "###############\n###...[=]@..###\n###.........###\n###############\n"
         );
-        robot.try_move('v');
-        robot.try_move('v');
-        robot.try_move('<');
-        robot.try_move('<');
+        assert!(warehouse.step(0, '<').is_some());
+        assert!(warehouse.step(0, '<').is_some());
+        assert!(warehouse.step(0, '<').is_some());
+        assert!(warehouse.step(0, '<').is_none());
+        assert_eq!(boxes(&warehouse), vec![IVec2::new(3, 1)]);
+        assert_eq!(warehouse.coordinate_sum(), 103);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let (mut warehouse, moves) = parse(INPUT1, 1);
+        let initial = warehouse.to_string();
+        let mut states = vec![initial.clone()];
+        for step in moves.chars() {
+            warehouse.step(0, step);
+            states.push(warehouse.to_string());
+        }
+        assert_eq!(warehouse.coordinate_sum(), 2028);
+
+        // Blocked moves are not part of the history
+        states.dedup();
+        for state in states.iter().rev().skip(1) {
+            assert!(warehouse.undo().is_some());
+            assert_eq!(&warehouse.to_string(), state);
+        }
+        assert_eq!(warehouse.undo(), None);
+        assert_eq!(warehouse.to_string(), initial);
+
+        while warehouse.redo().is_some() {}
+        assert_eq!(warehouse.coordinate_sum(), 2028);
+
+        // A new step discards the undone steps
+        warehouse.undo();
+        warehouse.step(0, '^');
+        assert_eq!(warehouse.redo(), None);
+    }
+
+    #[test]
+    fn test_multiple_robots() {
+        let mut warehouse = Warehouse::new("#######\n#@.O.@#\n#.....#\n#######", 1);
+        warehouse.run(&[">>>", "<v<"]);
+        // Robot 1 blocks the box until it steps aside
         assert_eq!(
-            robot.boxes,
-            HashMap::from([
-                (IVec2::new(5, 3), IVec2::new(6, 3)),
-                (IVec2::new(6, 3), IVec2::new(5, 3)),
-                (IVec2::new(7, 3), IVec2::new(8, 3)),
-                (IVec2::new(8, 3), IVec2::new(7, 3)),
-                (IVec2::new(6, 4), IVec2::new(7, 4)),
-                (IVec2::new(7, 4), IVec2::new(6, 4)),
-            ])
+            warehouse.to_string(),
+            "#######\n#..@O.#\n#..@..#\n#######\n"
         );
-        robot.try_move('^');
+        assert_eq!(warehouse.undo().map(|step| step.robot), Some(1));
+        assert_eq!(warehouse.undo().map(|step| step.robot), Some(0));
+    }
+
+    #[test]
+    fn test_replay() {
+        let warehouse = Warehouse::new("#####\n#@O.#\n#####", 1);
+        let mut debugger = Debugger::new(Replay::new(warehouse, ">>\n<"));
         assert_eq!(
-            robot.boxes,
-            HashMap::from([
-                (IVec2::new(5, 2), IVec2::new(6, 2)),
-                (IVec2::new(6, 2), IVec2::new(5, 2)),
-                (IVec2::new(7, 2), IVec2::new(8, 2)),
-                (IVec2::new(8, 2), IVec2::new(7, 2)),
-                (IVec2::new(6, 3), IVec2::new(7, 3)),
-                (IVec2::new(7, 3), IVec2::new(6, 3)),
-            ])
+            debugger.simulation.status(),
+            "start, robot at (1, 1), next > (3 left)"
         );
-        robot.try_move('^');
+        debugger.command("2");
         assert_eq!(
-            robot.boxes,
-            HashMap::from([
-                (IVec2::new(5, 2), IVec2::new(6, 2)),
-                (IVec2::new(6, 2), IVec2::new(5, 2)),
-                (IVec2::new(7, 2), IVec2::new(8, 2)),
-                (IVec2::new(8, 2), IVec2::new(7, 2)),
-                (IVec2::new(6, 3), IVec2::new(7, 3)),
-                (IVec2::new(7, 3), IVec2::new(6, 3)),
-            ])
+            debugger.simulation.status(),
+            "> blocked, robot at (2, 1), next < (1 left)"
         );
-        robot.try_move('<');
-        robot.try_move('<');
-        robot.try_move('^');
-        robot.try_move('^');
+        debugger.command("");
+        debugger.command("");
+        assert_eq!(debugger.step, 3);
+        debugger.command("-2");
         assert_eq!(
-            robot.boxes,
-            HashMap::from([
-                (IVec2::new(5, 1), IVec2::new(6, 1)),
-                (IVec2::new(6, 1), IVec2::new(5, 1)),
-                (IVec2::new(7, 2), IVec2::new(8, 2)),
-                (IVec2::new(8, 2), IVec2::new(7, 2)),
-                (IVec2::new(6, 3), IVec2::new(7, 3)),
-                (IVec2::new(7, 3), IVec2::new(6, 3)),
-            ])
+            debugger.simulation.warehouse.to_string(),
+            "#####\n#.@O#\n#####\n"
         );
+        debugger.command("b");
+        assert_eq!(
+            debugger.simulation.warehouse.to_string(),
+            "#####\n#@O.#\n#####\n"
+        );
+    }
+
+    #[test]
+    fn test_run_with() {
+        let mut warehouse = Warehouse::new("#####\n#@O.#\n#####", 1);
+        let mut states = Vec::new();
+        warehouse.run_with(&[">>\n<"], |warehouse| states.push(warehouse.robots[0].x));
+        // Initial state, then every move including the blocked second push
+        assert_eq!(states, vec![1, 2, 2, 1]);
     }
 }
@@ -0,0 +1,252 @@
+//! Warehouse with robots pushing boxes of any width.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+};
+
+use glam::IVec2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crate {
+    /// Left-most cell of the box
+    pub position: IVec2,
+    pub width: i32,
+}
+
+impl Crate {
+    fn cells(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (0..self.width).map(|dx| self.position + IVec2::new(dx, 0))
+    }
+
+    /// GPS coordinate of the box
+    pub fn coordinate(&self) -> i32 {
+        100 * self.position.y + self.position.x
+    }
+}
+
+/// What happened during a single step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// Index of the robot that moved, in `Warehouse::robots`
+    pub robot: usize,
+    pub direction: IVec2,
+    /// Robot position before the step
+    pub from: IVec2,
+    /// Indices of the boxes that were pushed, in `Warehouse::boxes`
+    pub pushed: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Warehouse {
+    pub robots: Vec<IVec2>,
+    pub walls: HashSet<IVec2>,
+    pub boxes: Vec<Crate>,
+    /// Index of the box occupying each cell
+    occupied: HashMap<IVec2, usize>,
+    pub width: i32,
+    pub height: i32,
+    /// Steps that can be undone, latest last
+    history: Vec<Step>,
+    /// Undone steps that can be redone, latest undo last
+    undone: Vec<Step>,
+}
+
+impl Warehouse {
+    /// Parse the map, with every tile stretched to `scale` cells horizontally.
+    pub fn new(grid: &str, scale: i32) -> Self {
+        let mut robots = Vec::new();
+        let mut walls = HashSet::new();
+        let mut boxes = Vec::new();
+        let (mut width, mut height) = (0, 0);
+        for (y, row) in grid.lines().enumerate() {
+            for (x, symbol) in row.chars().enumerate() {
+                let position = IVec2::new(x as i32 * scale, y as i32);
+                match symbol {
+                    '#' => walls.extend((0..scale).map(|dx| position + IVec2::new(dx, 0))),
+                    '@' => robots.push(position),
+                    'O' => boxes.push(Crate {
+                        position,
+                        width: scale,
+                    }),
+                    _ => {}
+                }
+                width = width.max((x as i32 + 1) * scale);
+            }
+            height = y as i32 + 1;
+        }
+        let mut warehouse = Self {
+            robots,
+            walls,
+            boxes,
+            occupied: HashMap::new(),
+            width,
+            height,
+            history: Vec::new(),
+            undone: Vec::new(),
+        };
+        for index in 0..warehouse.boxes.len() {
+            warehouse.occupy(index);
+        }
+        warehouse
+    }
+
+    fn occupy(&mut self, index: usize) {
+        for cell in self.boxes[index].cells() {
+            self.occupied.insert(cell, index);
+        }
+    }
+
+    fn vacate(&mut self, index: usize) {
+        for cell in self.boxes[index].cells() {
+            self.occupied.remove(&cell);
+        }
+    }
+
+    /// Box covering `position`, if any
+    pub fn box_at(&self, position: IVec2) -> Option<usize> {
+        self.occupied.get(&position).copied()
+    }
+
+    /// Try to move `robot`, no-op if not possible. Returns what moved, if anything did.
+    ///
+    /// Other robots block the way like walls.
+    pub fn step(&mut self, robot: usize, direction: char) -> Option<Step> {
+        let direction = match direction {
+            '^' => IVec2::NEG_Y,
+            '>' => IVec2::X,
+            'v' => IVec2::Y,
+            '<' => IVec2::NEG_X,
+            _ => return None,
+        };
+        let from = self.robots[robot];
+        let pushed = self.pushed_boxes(from + direction, direction)?;
+        let step = Step {
+            robot,
+            direction,
+            from,
+            pushed,
+        };
+        self.apply(&step, direction);
+        self.history.push(step.clone());
+        self.undone.clear();
+        Some(step)
+    }
+
+    /// Execute the move strings of all robots interleaved, one move per robot
+    /// at a time. Characters that are not moves are skipped.
+    pub fn run(&mut self, moves: &[&str]) {
+        self.run_with(moves, |_| {});
+    }
+
+    /// Like `run`, calling `on_step` with the warehouse before the first move
+    /// and after every move, including moves that were blocked.
+    pub fn run_with(&mut self, moves: &[&str], mut on_step: impl FnMut(&Warehouse)) {
+        on_step(self);
+        let mut moves: Vec<_> = moves
+            .iter()
+            .map(|moves| moves.chars().filter(|c| "^>v<".contains(*c)))
+            .collect();
+        loop {
+            let mut any = false;
+            for (robot, moves) in moves.iter_mut().enumerate() {
+                if let Some(direction) = moves.next() {
+                    any = true;
+                    self.step(robot, direction);
+                    on_step(self);
+                }
+            }
+            if !any {
+                break;
+            }
+        }
+    }
+
+    /// Revert the latest step, returns it if there was one.
+    pub fn undo(&mut self) -> Option<Step> {
+        let step = self.history.pop()?;
+        self.apply(&step, -step.direction);
+        self.undone.push(step.clone());
+        Some(step)
+    }
+
+    /// Repeat the latest undone step, returns it if there was one.
+    pub fn redo(&mut self) -> Option<Step> {
+        let step = self.undone.pop()?;
+        self.apply(&step, step.direction);
+        self.history.push(step.clone());
+        Some(step)
+    }
+
+    /// Move the robot and boxes of `step` by `offset`
+    fn apply(&mut self, step: &Step, offset: IVec2) {
+        for &index in &step.pushed {
+            self.vacate(index);
+        }
+        for &index in &step.pushed {
+            self.boxes[index].position += offset;
+            self.occupy(index);
+        }
+        self.robots[step.robot] += offset;
+    }
+
+    /// All boxes that get pushed when something enters `target`, or `None` if
+    /// a wall blocks the way.
+    fn pushed_boxes(&self, target: IVec2, direction: IVec2) -> Option<Vec<usize>> {
+        let mut pushed = Vec::new();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([target]);
+
+        while let Some(cell) = queue.pop_front() {
+            if self.walls.contains(&cell) || self.robots.contains(&cell) {
+                return None;
+            }
+            let Some(index) = self.box_at(cell) else {
+                continue;
+            };
+            if !seen.insert(index) {
+                continue;
+            }
+            pushed.push(index);
+            for next in self.boxes[index].cells().map(|cell| cell + direction) {
+                if self.box_at(next) != Some(index) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        Some(pushed)
+    }
+
+    pub fn coordinate_sum(&self) -> i32 {
+        self.boxes.iter().map(Crate::coordinate).sum()
+    }
+}
+
+impl fmt::Display for Warehouse {
+    /// Map in the puzzle format, boxes wider than one cell are drawn as `[]`, `[=]`, ...
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let position = IVec2::new(x, y);
+                let symbol = if self.robots.contains(&position) {
+                    '@'
+                } else if self.walls.contains(&position) {
+                    '#'
+                } else if let Some(index) = self.box_at(position) {
+                    let item = self.boxes[index];
+                    match position.x - item.position.x {
+                        _ if item.width == 1 => 'O',
+                        0 => '[',
+                        dx if dx == item.width - 1 => ']',
+                        _ => '=',
+                    }
+                } else {
+                    '.'
+                };
+                write!(f, "{symbol}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
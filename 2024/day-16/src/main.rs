@@ -1,164 +1,198 @@
 use std::{
     cmp::Reverse,
     collections::{BinaryHeap, HashMap, HashSet, VecDeque},
-    fs,
+    env, fs,
     path::Path,
+    str::FromStr,
     time::Instant,
 };
 
 use glam::IVec2;
+use render::{Canvas, Cell, Colour, Renderer};
 
 const DIRECTIONS: [IVec2; 4] = [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y];
 
 fn main() {
     let path = Path::new("input.txt");
     let input = parse(&fs::read_to_string(path).expect("Cannot solve without input!"));
+    let args: Vec<String> = env::args().collect();
 
+    // Other costs from `--costs FORWARD,TURN[,REVERSE]`
+    let costs = match args.iter().position(|arg| arg == "--costs") {
+        Some(index) => args
+            .get(index + 1)
+            .expect("Costs should be given as FORWARD,TURN[,REVERSE]")
+            .parse()
+            .unwrap_or_else(|error| panic!("{error}")),
+        None => COSTS,
+    };
+
+    // Both parts come from the same search
     let start_1 = Instant::now();
-    println!("Part 1: {}, {:?}", part_1(&input), start_1.elapsed());
+    let search = input.search(&costs);
+    println!("Part 1: {}, {:?}", part_1(&search), start_1.elapsed());
     let start_2 = Instant::now();
-    println!("Part 2: {}, {:?}", part_2(&input), start_2.elapsed());
-}
+    println!("Part 2: {}, {:?}", part_2(&search), start_2.elapsed());
 
-/// Dijkstra
-fn part_1(graph: &Graph) -> i32 {
-    let mut distances = graph.init_distances();
-    let mut queue = BinaryHeap::new();
-    queue.push(HeapEntry::new(0, graph.start));
-
-    while !queue.is_empty() {
-        let current = queue.pop().expect("while !queue.is_empty()");
-        for (new, weight) in graph.neighbours(current.node) {
-            let node_distance = distances
-                .get(&new)
-                .expect("all nodes should have a distance");
-            let new_distance = distances
-                .get(&current.node)
-                .expect("all nodes should have a distance")
-                + weight;
-            if new_distance < *node_distance {
-                distances.insert(new, new_distance);
-                queue.push(HeapEntry::new(new_distance, new));
-            }
-        }
+    if args.iter().any(|arg| arg == "--path") {
+        let path: Vec<String> = search
+            .optimal_path()
+            .iter()
+            .map(|pos| format!("{},{}", pos.x, pos.y))
+            .collect();
+        println!("Optimal path: {}", path.join(" "));
     }
 
-    *distances
-        .iter()
-        .filter(|(node, _)| node.pos == graph.end)
-        .map(|(_, distance)| distance)
-        .min()
-        .expect("Should have an answer")
+    if args.iter().any(|arg| arg == "--render") {
+        let status = format!(
+            "Score {}, {} tiles on optimal paths",
+            search.best_score().expect("Should have an answer"),
+            search.optimal_tiles().len()
+        );
+        Renderer::new()
+            .legend(WALL, "wall")
+            .legend(PATH, "optimal path")
+            .legend(TILE, "on another optimal path")
+            .draw(&canvas(&input, &search), &status)
+            .expect("Cannot write to stdout!");
+    }
 }
 
-/// Dijkstra with tracing of all best paths
-fn part_2(graph: &Graph) -> i32 {
-    let mut distances = graph.init_distances();
-    let mut previous = graph.init_previous();
-    let mut queue = BinaryHeap::new();
-    queue.push(HeapEntry::new(0, graph.start));
-
-    while !queue.is_empty() {
-        let current = queue.pop().expect("while !queue.is_empty()");
-        for (new, weight) in graph.neighbours(current.node) {
-            let node_distance = distances
-                .get(&new)
-                .expect("all nodes should have a distance");
-            let new_distance = distances
-                .get(&current.node)
-                .expect("all nodes should have a distance")
-                + weight;
-
-            use std::cmp::Ordering::*;
-            match new_distance.cmp(node_distance) {
-                Less => {
-                    previous.insert(new, Some(vec![current.node]));
-                    distances.insert(new, new_distance);
-                    queue.push(HeapEntry::new(new_distance, new));
-                }
-                Equal => {
-                    if let Some(Some(nodes)) = previous.get_mut(&new) {
-                        nodes.push(current.node);
-                    }
-                }
-                Greater => {}
+fn part_1(search: &Search) -> i32 {
+    search.best_score().expect("Should have an answer")
+}
+
+fn part_2(search: &Search) -> i32 {
+    search.optimal_tiles().len() as i32
+}
+
+/// Cost of each action the reindeer can take.
+#[derive(Debug, Clone, Copy)]
+struct Costs {
+    forward: i32,
+    /// Turning 90 degrees
+    turn: i32,
+    /// Turning 180 degrees at once, `None` if it takes two turns
+    reverse: Option<i32>,
+}
+
+impl Costs {
+    /// Costs for a search, which needs every action to cost something so
+    /// that paths cannot loop back to where they started for free.
+    fn new(forward: i32, turn: i32, reverse: Option<i32>) -> Result<Self, String> {
+        for (action, cost) in [
+            ("forward", Some(forward)),
+            ("turn", Some(turn)),
+            ("reverse", reverse),
+        ] {
+            if let Some(cost) = cost.filter(|&cost| cost <= 0) {
+                return Err(format!("Cost of {action} should be positive, not {cost}"));
             }
         }
+        Ok(Self {
+            forward,
+            turn,
+            reverse,
+        })
     }
-    let path = get_path(&previous, &distances, graph.start, graph.end);
-    path.len() as i32
 }
 
-fn get_path(
-    previous: &HashMap<Node, Option<Vec<Node>>>,
-    distances: &HashMap<Node, i32>,
-    start: Node,
-    end_pos: IVec2,
-) -> HashSet<IVec2> {
-    let mut min = i32::MAX;
-    let mut all_goals = Vec::new();
-    for node in DIRECTIONS.map(|dir| Node::new(end_pos, dir)) {
-        use std::cmp::Ordering::*;
-        let dist = distances.get(&node).unwrap();
-        match dist.cmp(&min) {
-            Less => {
-                min = *dist;
-                all_goals = vec![node];
-            }
-            Equal => all_goals.push(node),
-            Greater => {}
+impl FromStr for Costs {
+    type Err = String;
+
+    /// Parse `FORWARD,TURN` or `FORWARD,TURN,REVERSE`, e.g. `1,1000`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let costs: Vec<i32> = s
+            .split(',')
+            .map(|cost| cost.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Invalid costs: {s}"))?;
+        match costs[..] {
+            [forward, turn] => Costs::new(forward, turn, None),
+            [forward, turn, reverse] => Costs::new(forward, turn, Some(reverse)),
+            _ => Err(format!(
+                "Invalid costs: {s}, expected FORWARD,TURN[,REVERSE]"
+            )),
         }
     }
-    let mut visited = HashSet::new();
-    let mut queue = VecDeque::from(all_goals);
-    while !queue.is_empty() {
-        let node = queue.pop_front().expect("while !queue.is_empty()");
-        visited.insert(node);
-        if let Some(Some(prev)) = previous.get(&node) {
-            for option in prev {
-                if !visited.contains(option) {
-                    queue.push_back(*option);
-                }
+}
+
+const COSTS: Costs = Costs {
+    forward: 1,
+    turn: 1000,
+    reverse: None,
+};
+
+const WALL: Cell = Cell::new('#').fg(Colour::Grey);
+const PATH: Cell = Cell::new('>').fg(Colour::BrightGreen);
+const TILE: Cell = Cell::new('O').fg(Colour::Blue);
+
+/// The maze with one optimal path drawn as arrows, and the remaining tiles of
+/// all optimal paths
+fn canvas(graph: &Graph, search: &Search) -> Canvas {
+    let height = graph.tiles.len();
+    let width = graph.tiles.first().map_or(0, Vec::len);
+    let mut canvas = Canvas::new(width, height, Cell::EMPTY);
+    for (y, row) in graph.tiles.iter().enumerate() {
+        for (x, &open) in row.iter().enumerate() {
+            if !open {
+                canvas.set(x, y, WALL);
             }
-        } else if node != start {
-            panic!("Path didn't start from the Start node!");
         }
     }
-    visited.iter().map(|node| node.pos).collect()
+
+    for tile in search.optimal_tiles() {
+        canvas.set(tile.x as usize, tile.y as usize, TILE);
+    }
+    for step in search.optimal_path().windows(2) {
+        let symbol = match step[1] - step[0] {
+            IVec2::X => '>',
+            IVec2::Y => 'v',
+            IVec2::NEG_X => '<',
+            _ => '^',
+        };
+        canvas.set(
+            step[0].x as usize,
+            step[0].y as usize,
+            Cell::new(symbol).fg(Colour::BrightGreen),
+        );
+    }
+
+    let start = graph.start.pos;
+    canvas.set(
+        start.x as usize,
+        start.y as usize,
+        Cell::new('S').fg(Colour::BrightYellow),
+    );
+    canvas.set(
+        graph.end.x as usize,
+        graph.end.y as usize,
+        Cell::new('E').fg(Colour::BrightYellow),
+    );
+    canvas
 }
 
 fn parse(input: &str) -> Graph {
-    let mut nodes = HashSet::new();
+    let mut tiles = Vec::new();
     let mut start = Node::new(IVec2::ZERO, IVec2::X);
     let mut end = IVec2::ZERO;
 
     for (i, row) in input.lines().enumerate() {
+        let mut tile_row = Vec::new();
         for (j, c) in row.chars().enumerate() {
             match c {
-                '.' => {
-                    for dir in DIRECTIONS {
-                        let _ = nodes.insert(Node::new(IVec2::new(j as i32, i as i32), dir));
-                    }
-                }
-                'S' => {
-                    start = Node::new(IVec2::new(j as i32, i as i32), IVec2::X);
-                    for dir in DIRECTIONS {
-                        let _ = nodes.insert(Node::new(IVec2::new(j as i32, i as i32), dir));
-                    }
-                }
-                'E' => {
-                    end = IVec2::new(j as i32, i as i32);
-                    for dir in DIRECTIONS {
-                        let _ = nodes.insert(Node::new(IVec2::new(j as i32, i as i32), dir));
-                    }
-                }
+                '.' => {}
+                'S' => start = Node::new(IVec2::new(j as i32, i as i32), IVec2::X),
+                'E' => end = IVec2::new(j as i32, i as i32),
                 '#' => {}
                 other => panic!("Unexpected character {other}!"),
             }
+            tile_row.push(c != '#');
         }
+        tiles.push(tile_row);
     }
 
-    Graph { nodes, start, end }
+    Graph { tiles, start, end }
 }
 
 /// Needed for proper sorting of BinaryHeap
@@ -208,42 +242,148 @@ impl Node {
 }
 
 struct Graph {
-    nodes: HashSet<Node>,
+    /// Whether each tile, indexed as `tiles[y][x]`, can be walked on
+    tiles: Vec<Vec<bool>>,
     start: Node,
     end: IVec2,
 }
 
 impl Graph {
-    fn neighbours(&self, node: Node) -> Vec<(Node, i32)> {
+    fn is_open(&self, position: IVec2) -> bool {
+        position.x >= 0
+            && position.y >= 0
+            && self
+                .tiles
+                .get(position.y as usize)
+                .and_then(|row| row.get(position.x as usize))
+                .is_some_and(|&open| open)
+    }
+
+    /// Nodes reachable with a single action, generated on demand
+    fn neighbours(&self, node: Node, costs: &Costs) -> Vec<(Node, i32)> {
         let mut neighbours = Vec::new();
-        if self.nodes.contains(&node.next()) {
-            neighbours.push((node.next(), 1));
+        if self.is_open(node.next().pos) {
+            neighbours.push((node.next(), costs.forward));
         }
-        // Add 90 degree rotations, continue if 0 or 180 degrees
         for new_dir in DIRECTIONS {
-            if new_dir == node.dir || new_dir == -node.dir {
+            if new_dir == node.dir {
                 continue;
             }
-            neighbours.push((Node::new(node.pos, new_dir), 1000));
+            let cost = if new_dir == -node.dir {
+                match costs.reverse {
+                    Some(cost) => cost,
+                    None => continue,
+                }
+            } else {
+                costs.turn
+            };
+            neighbours.push((Node::new(node.pos, new_dir), cost));
         }
         neighbours
     }
 
-    fn init_distances(&self) -> HashMap<Node, i32> {
-        self.nodes
-            .iter()
-            .map(|&node| {
-                if node == self.start {
-                    (node, 0)
-                } else {
-                    (node, i32::MAX)
+    /// Dijkstra with tracing of all best paths
+    fn search(&self, costs: &Costs) -> Search {
+        let mut distances = HashMap::from([(self.start, 0)]);
+        let mut previous: HashMap<Node, Vec<Node>> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        queue.push(HeapEntry::new(0, self.start));
+
+        while let Some(current) = queue.pop() {
+            if current.distance > distances[&current.node] {
+                continue;
+            }
+            for (new, weight) in self.neighbours(current.node, costs) {
+                let new_distance = current.distance + weight;
+                let node_distance = distances.get(&new).copied().unwrap_or(i32::MAX);
+
+                use std::cmp::Ordering::*;
+                match new_distance.cmp(&node_distance) {
+                    Less => {
+                        previous.insert(new, vec![current.node]);
+                        distances.insert(new, new_distance);
+                        queue.push(HeapEntry::new(new_distance, new));
+                    }
+                    Equal => previous.entry(new).or_default().push(current.node),
+                    Greater => {}
                 }
-            })
-            .collect()
+            }
+        }
+
+        let best = DIRECTIONS
+            .iter()
+            .filter_map(|&dir| distances.get(&Node::new(self.end, dir)))
+            .min()
+            .copied();
+        let goals = DIRECTIONS
+            .iter()
+            .map(|&dir| Node::new(self.end, dir))
+            .filter(|node| best.is_some() && distances.get(node).copied() == best)
+            .collect();
+        Search {
+            best,
+            goals,
+            previous,
+        }
+    }
+}
+
+/// Result of the search from the start to the end tile.
+struct Search {
+    best: Option<i32>,
+    /// End nodes reached with the best score
+    goals: Vec<Node>,
+    /// Predecessors of each node on the best paths to it
+    previous: HashMap<Node, Vec<Node>>,
+}
+
+impl Search {
+    fn best_score(&self) -> Option<i32> {
+        self.best
+    }
+
+    /// Positions along one optimal path, from start to end
+    fn optimal_path(&self) -> Vec<IVec2> {
+        let Some(&goal) = self.goals.first() else {
+            return Vec::new();
+        };
+        let mut path = vec![goal.pos];
+        let mut visited = HashSet::from([goal]);
+        let mut node = goal;
+        while let Some(&prev) = self.previous.get(&node).and_then(|nodes| nodes.first()) {
+            // Stop at the start even if predecessors form a cycle
+            if !visited.insert(prev) {
+                break;
+            }
+            // Turning in place does not change the position
+            if prev.pos != node.pos {
+                path.push(prev.pos);
+            }
+            node = prev;
+        }
+        path.reverse();
+        path
     }
 
-    fn init_previous(&self) -> HashMap<Node, Option<Vec<Node>>> {
-        self.nodes.iter().map(|&node| (node, None)).collect()
+    /// All positions that are on at least one optimal path, sorted by row
+    fn optimal_tiles(&self) -> Vec<IVec2> {
+        let mut visited: HashSet<Node> = self.goals.iter().copied().collect();
+        let mut queue: VecDeque<Node> = self.goals.iter().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            for &prev in self.previous.get(&node).into_iter().flatten() {
+                if visited.insert(prev) {
+                    queue.push_back(prev);
+                }
+            }
+        }
+        let mut tiles: Vec<IVec2> = visited
+            .iter()
+            .map(|node| node.pos)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        tiles.sort_by_key(|pos| (pos.y, pos.x));
+        tiles
     }
 }
 
@@ -309,16 +449,75 @@ mod tests {
     #[test]
     fn test_part_1() {
         let graph = parse(INPUT1);
-        assert_eq!(part_1(&graph), 7036);
+        assert_eq!(part_1(&graph.search(&COSTS)), 7036);
         let graph = parse(INPUT2);
-        assert_eq!(part_1(&graph), 11048);
+        assert_eq!(part_1(&graph.search(&COSTS)), 11048);
     }
 
     #[test]
     fn test_part_2() {
         let graph = parse(INPUT1);
-        assert_eq!(part_2(&graph), 45);
+        assert_eq!(part_2(&graph.search(&COSTS)), 45);
         let graph = parse(INPUT2);
-        assert_eq!(part_2(&graph), 64);
+        assert_eq!(part_2(&graph.search(&COSTS)), 64);
+    }
+
+    #[test]
+    fn test_optimal_path() {
+        let graph = parse(INPUT1);
+        let search = graph.search(&COSTS);
+        let path = search.optimal_path();
+        assert_eq!(path.first(), Some(&graph.start.pos));
+        assert_eq!(path.last(), Some(&graph.end));
+        // 36 steps forward, every step is to a neighbouring tile
+        assert_eq!(path.len(), 37);
+        assert!(path
+            .windows(2)
+            .all(|pair| (pair[1] - pair[0]).abs().element_sum() == 1));
+        assert!(path.iter().all(|pos| search.optimal_tiles().contains(pos)));
+    }
+
+    #[test]
+    fn test_costs() {
+        let graph = parse("#####\n#E.S#\n#####");
+        assert_eq!(graph.search(&COSTS).best_score(), Some(2002));
+        let reverse = Costs::new(1, 1000, Some(500)).unwrap();
+        assert_eq!(graph.search(&reverse).best_score(), Some(502));
+        let cheap_turns = Costs::new(10, 1, None).unwrap();
+        assert_eq!(graph.search(&cheap_turns).best_score(), Some(22));
+        assert_eq!(
+            graph.search(&COSTS).optimal_path(),
+            vec![IVec2::new(3, 1), IVec2::new(2, 1), IVec2::new(1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_invalid_costs() {
+        assert!(Costs::new(0, 1000, None).is_err());
+        assert!(Costs::new(1, 0, None).is_err());
+        assert!(Costs::new(1, 1000, Some(0)).is_err());
+        assert!(Costs::new(-1, 1000, None).is_err());
+        assert!(Costs::new(1, -1000, Some(500)).is_err());
+        assert!(Costs::new(1, 1000, Some(-5)).is_err());
+        assert!("0,1000".parse::<Costs>().is_err());
+        assert!("1,1000,x".parse::<Costs>().is_err());
+        assert!("1".parse::<Costs>().is_err());
+        assert_eq!("1,1000".parse::<Costs>().unwrap().turn, 1000);
+        assert_eq!("1, 1000, 500".parse::<Costs>().unwrap().reverse, Some(500));
+    }
+
+    #[test]
+    fn test_optimal_path_cycle() {
+        // Free turns make turning nodes each other's predecessors, the walk
+        // back must still end
+        let graph = parse("#####\n#E.S#\n#####");
+        let free_turns = Costs {
+            forward: 1,
+            turn: 0,
+            reverse: None,
+        };
+        let path = graph.search(&free_turns).optimal_path();
+        assert_eq!(path.first(), Some(&graph.start.pos));
+        assert_eq!(path.last(), Some(&graph.end));
     }
 }
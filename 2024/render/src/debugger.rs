@@ -0,0 +1,322 @@
+//! Stepping through a simulation in the terminal, forwards and backwards.
+//!
+//! In a terminal every key press is handled as it comes, in raw mode. When
+//! stdin is not a terminal, commands are read as lines instead, so a session
+//! can be scripted.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, terminal,
+};
+
+use crate::{Canvas, Cell, Colour, Grid, Renderer};
+
+const KEYS: &str = "→ / Enter: step, ← / b: back, digits then Enter: jump N steps (- to go back), g digits Enter: go to step N, Home / End: first / last step, q: quit";
+const LINES: &str = "Empty line: step, b: back, N / -N: jump N steps, g N: go to step N, q: quit";
+
+/// A simulation that can be stepped in both directions.
+pub trait Simulation {
+    /// Take the next step, false if the simulation has ended
+    fn forward(&mut self) -> bool;
+    /// Revert the latest step, false if at the start
+    fn back(&mut self) -> bool;
+    fn canvas(&self) -> Canvas;
+    /// Current move and position, shown below the grid
+    fn status(&self) -> String;
+}
+
+/// Keeps track of the step and what changed in it.
+pub struct Debugger<S> {
+    pub simulation: S,
+    pub step: usize,
+    /// Canvas before the latest command, to find the changed cells
+    previous: Canvas,
+    /// Jump typed so far, e.g. `-12` or `g 40`
+    pending: String,
+    help: &'static str,
+}
+
+impl<S: Simulation> Debugger<S> {
+    pub fn new(simulation: S) -> Self {
+        Self {
+            previous: simulation.canvas(),
+            simulation,
+            step: 0,
+            pending: String::new(),
+            help: LINES,
+        }
+    }
+
+    /// Step with single key presses until `q`, drawing the simulation after
+    /// each one. Reads lines from stdin instead if it is not a terminal.
+    pub fn run(&mut self, renderer: &Renderer) -> io::Result<()> {
+        if !io::stdin().is_terminal() {
+            return self.run_lines(renderer, io::stdin().lock());
+        }
+        let _raw_mode = RawMode::enable()?;
+        self.help = KEYS;
+        loop {
+            // Raw mode does not return to the start of the line by itself
+            let frame = renderer.render(&self.canvas(), &self.status());
+            let mut stdout = io::stdout().lock();
+            execute!(
+                stdout,
+                terminal::Clear(terminal::ClearType::All),
+                cursor::MoveTo(0, 0)
+            )?;
+            stdout.write_all(frame.replace('\n', "\r\n").as_bytes())?;
+            stdout.flush()?;
+
+            // Anything else, like resizing the terminal, only redraws
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Release && !self.key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Read commands from `input` and draw the simulation after each one,
+    /// until `q` or the end of input.
+    pub fn run_lines(&mut self, renderer: &Renderer, input: impl BufRead) -> io::Result<()> {
+        self.help = LINES;
+        renderer.draw(&self.canvas(), &self.status())?;
+        for line in input.lines() {
+            if !self.command(&line?) {
+                break;
+            }
+            renderer.draw(&self.canvas(), &self.status())?;
+        }
+        Ok(())
+    }
+
+    /// Handle one key press, false to quit. Digits, `-` and `g` are collected
+    /// until Enter runs them as a jump.
+    pub fn key(&mut self, key: KeyEvent) -> bool {
+        let command = match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char('q') => return false,
+            KeyCode::Char(digit @ '0'..='9') => {
+                self.pending.push(digit);
+                return true;
+            }
+            KeyCode::Char('-') if self.pending.is_empty() => {
+                self.pending.push('-');
+                return true;
+            }
+            KeyCode::Char('g') if self.pending.is_empty() => {
+                self.pending.push_str("g ");
+                return true;
+            }
+            KeyCode::Backspace => {
+                self.pending.pop();
+                if self.pending == "g" {
+                    self.pending.clear();
+                }
+                return true;
+            }
+            KeyCode::Esc => {
+                self.pending.clear();
+                return true;
+            }
+            KeyCode::Enter => std::mem::take(&mut self.pending),
+            KeyCode::Right | KeyCode::Char(' ' | 'n') => String::new(),
+            KeyCode::Left | KeyCode::Char('b') => "b".to_string(),
+            KeyCode::Home => "g 0".to_string(),
+            KeyCode::End => format!("g {}", i64::MAX),
+            _ => return true,
+        };
+        self.pending.clear();
+        self.command(&command)
+    }
+
+    /// Handle one command, false to quit
+    pub fn command(&mut self, command: &str) -> bool {
+        self.previous = self.simulation.canvas();
+        let command = command.trim();
+        let target = match command {
+            "q" => return false,
+            "" | "n" => Some(self.step as i64 + 1),
+            "b" => Some(self.step as i64 - 1),
+            _ => match command.strip_prefix('g') {
+                Some(step) => step.trim().parse().ok(),
+                None => command.parse().ok().map(|n: i64| self.step as i64 + n),
+            },
+        };
+        if let Some(target) = target {
+            self.go_to(target.max(0) as usize);
+        }
+        true
+    }
+
+    /// Step forwards or backwards until at `target`, or the end of the simulation
+    fn go_to(&mut self, target: usize) {
+        while self.step < target && self.simulation.forward() {
+            self.step += 1;
+        }
+        while self.step > target && self.simulation.back() {
+            self.step -= 1;
+        }
+    }
+
+    /// Current state, with the cells changed by the latest command highlighted
+    pub fn canvas(&self) -> Canvas {
+        let mut canvas = self.simulation.canvas();
+        for y in 0..canvas.height() {
+            for x in 0..canvas.width() {
+                let cell = canvas.cell(x, y);
+                if self.previous.get(x, y) != Some(cell) {
+                    canvas.set(
+                        x,
+                        y,
+                        Cell {
+                            background: Some(Colour::Magenta),
+                            ..cell
+                        },
+                    );
+                }
+            }
+        }
+        canvas
+    }
+
+    fn status(&self) -> String {
+        let pending = if self.pending.is_empty() {
+            String::new()
+        } else {
+            format!("Jump: {}_\n", self.pending)
+        };
+        format!(
+            "Step {}: {}\n{pending}{}",
+            self.step,
+            self.simulation.status(),
+            self.help
+        )
+    }
+}
+
+/// Raw mode on the alternate screen, restored when dropped, also on panic.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let raw_mode = RawMode;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(raw_mode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // Nothing more can be done if restoring fails
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A dot walking right along a line of five cells
+    struct Walk(usize);
+
+    impl Simulation for Walk {
+        fn forward(&mut self) -> bool {
+            if self.0 == 4 {
+                return false;
+            }
+            self.0 += 1;
+            true
+        }
+
+        fn back(&mut self) -> bool {
+            if self.0 == 0 {
+                return false;
+            }
+            self.0 -= 1;
+            true
+        }
+
+        fn canvas(&self) -> Canvas {
+            let mut canvas = Canvas::new(5, 1, Cell::EMPTY);
+            canvas.set(self.0, 0, Cell::new('@'));
+            canvas
+        }
+
+        fn status(&self) -> String {
+            format!("at {}", self.0)
+        }
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut debugger = Debugger::new(Walk(0));
+        assert!(debugger.command(""));
+        assert_eq!((debugger.step, debugger.simulation.0), (1, 1));
+        debugger.command("2");
+        assert_eq!((debugger.step, debugger.simulation.0), (3, 3));
+        debugger.command("b");
+        assert_eq!(debugger.step, 2);
+        debugger.command("g 10");
+        assert_eq!((debugger.step, debugger.simulation.0), (4, 4));
+        debugger.command("-10");
+        assert_eq!(debugger.step, 0);
+        debugger.command("nonsense");
+        assert_eq!(debugger.step, 0);
+        assert!(!debugger.command("q"));
+    }
+
+    #[test]
+    fn test_keys() {
+        let mut debugger = Debugger::new(Walk(0));
+        let mut press = |code| debugger.key(KeyEvent::from(code));
+        assert!(press(KeyCode::Right));
+        assert!(press(KeyCode::Enter));
+        press(KeyCode::Left);
+        assert_eq!((debugger.step, debugger.simulation.0), (1, 1));
+
+        // Typed jumps only run on Enter
+        debugger.key(KeyEvent::from(KeyCode::Char('2')));
+        assert_eq!(debugger.pending, "2");
+        assert!(debugger.status().contains("Jump: 2_"));
+        assert_eq!(debugger.step, 1);
+        debugger.key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!((debugger.step, debugger.pending.as_str()), (3, ""));
+        for code in [KeyCode::Char('-'), KeyCode::Char('1'), KeyCode::Enter] {
+            debugger.key(KeyEvent::from(code));
+        }
+        assert_eq!(debugger.step, 2);
+        for code in [KeyCode::Char('g'), KeyCode::Char('9'), KeyCode::Backspace] {
+            debugger.key(KeyEvent::from(code));
+        }
+        assert_eq!(debugger.pending, "g ");
+        debugger.key(KeyEvent::from(KeyCode::Char('0')));
+        debugger.key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(debugger.step, 0);
+        debugger.key(KeyEvent::from(KeyCode::End));
+        assert_eq!(debugger.step, 4);
+        debugger.key(KeyEvent::from(KeyCode::Home));
+        assert_eq!(debugger.step, 0);
+
+        debugger.key(KeyEvent::from(KeyCode::Char('5')));
+        debugger.key(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(debugger.pending, "");
+        assert!(!debugger.key(KeyEvent::from(KeyCode::Char('q'))));
+        assert!(!debugger.key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+    }
+
+    #[test]
+    fn test_highlight() {
+        let mut debugger = Debugger::new(Walk(0));
+        debugger.command("");
+        let canvas = debugger.canvas();
+        assert_eq!(canvas.cell(0, 0), Cell::EMPTY.bg(Colour::Magenta));
+        assert_eq!(canvas.cell(1, 0), Cell::new('@').bg(Colour::Magenta));
+        assert_eq!(canvas.cell(2, 0), Cell::EMPTY);
+    }
+}
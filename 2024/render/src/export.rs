@@ -0,0 +1,469 @@
+//! Saving grids as images, either a PNG per frame or one animated GIF.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufWriter},
+    iter,
+    path::PathBuf,
+    str::FromStr,
+};
+
+use crate::{Cell, Colour, Grid};
+
+type Rgb = [u8; 3];
+
+/// RGB values of the colours. Blank cells without a background use
+/// `background`, and cells without a colour use `foreground`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pub background: Rgb,
+    pub foreground: Rgb,
+    pub colours: HashMap<Colour, Rgb>,
+}
+
+impl Default for Palette {
+    /// Colours of the Advent of Code website, with the usual terminal colours
+    fn default() -> Self {
+        let colours = Colour::ALL.into_iter().zip([
+            [0x00, 0x00, 0x00],
+            [0xcd, 0x31, 0x31],
+            [0x0d, 0xbc, 0x79],
+            [0xe5, 0xe5, 0x10],
+            [0x24, 0x72, 0xc8],
+            [0xbc, 0x3f, 0xbc],
+            [0x11, 0xa8, 0xcd],
+            [0xe5, 0xe5, 0xe5],
+            [0x66, 0x66, 0x66],
+            [0xf1, 0x4c, 0x4c],
+            [0x23, 0xd1, 0x8b],
+            [0xff, 0xff, 0x66],
+            [0x3b, 0x8e, 0xea],
+            [0xd6, 0x70, 0xd6],
+            [0x29, 0xb8, 0xdb],
+            [0xff, 0xff, 0xff],
+        ]);
+        Self {
+            background: [0x0f, 0x0f, 0x23],
+            foreground: [0xcc, 0xcc, 0xcc],
+            colours: colours.collect(),
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// Changes to the default palette as `NAME=RRGGBB` separated by commas,
+    /// e.g. `background=000000,bright_red=ff0000`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut palette = Self::default();
+        for entry in s.split(',').filter(|entry| !entry.is_empty()) {
+            let (name, hex) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid palette entry: {entry}"))?;
+            let rgb = match u32::from_str_radix(hex.trim_start_matches('#'), 16) {
+                Ok(value) if hex.trim_start_matches('#').len() == 6 => {
+                    let [_, r, g, b] = value.to_be_bytes();
+                    [r, g, b]
+                }
+                _ => return Err(format!("Invalid colour: {hex}")),
+            };
+            let name = name.replace('_', "");
+            if name.eq_ignore_ascii_case("background") {
+                palette.background = rgb;
+            } else if name.eq_ignore_ascii_case("foreground") {
+                palette.foreground = rgb;
+            } else {
+                let colour = Colour::ALL
+                    .into_iter()
+                    .find(|colour| format!("{colour:?}").eq_ignore_ascii_case(&name))
+                    .ok_or_else(|| format!("Unknown colour: {name}"))?;
+                palette.colours.insert(colour, rgb);
+            }
+        }
+        Ok(palette)
+    }
+}
+
+impl Palette {
+    /// Every colour the palette can produce, in a fixed order
+    fn table(&self) -> Vec<Rgb> {
+        let mut table = vec![self.background, self.foreground];
+        table.extend(Colour::ALL.iter().map(|colour| self.colours[colour]));
+        table
+    }
+
+    /// Index of the colour of `cell` in `table`
+    fn index(&self, cell: Cell) -> u8 {
+        let colour = match (cell.background, cell.foreground) {
+            (Some(colour), _) => colour,
+            _ if cell.is_blank() => return 0,
+            (None, Some(colour)) => colour,
+            (None, None) => return 1,
+        };
+        2 + Colour::ALL.iter().position(|&c| c == colour).unwrap() as u8
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Numbered `frame-NNNNN.png` files
+    Png,
+    /// A single `animation.gif`
+    Gif,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "png" => Ok(Format::Png),
+            "gif" => Ok(Format::Gif),
+            _ => Err(format!("Unknown image format: {s}")),
+        }
+    }
+}
+
+/// Writes grids as images to a directory, every cell as a square of pixels.
+pub struct Exporter {
+    directory: PathBuf,
+    format: Format,
+    palette: Palette,
+    /// Width and height of a cell in pixels
+    scale: u16,
+    /// Only one frame out of every `every` is kept
+    every: usize,
+    /// Most frames written, later ones are dropped
+    limit: Option<usize>,
+    /// Time each GIF frame is shown, in hundredths of a second
+    delay: u16,
+    /// Frames offered so far, including skipped ones
+    offered: usize,
+    /// Whether the latest offered frame was skipped
+    skipped: bool,
+    written: usize,
+    gif: Option<gif::Encoder<BufWriter<File>>>,
+}
+
+impl Exporter {
+    pub fn new(directory: impl Into<PathBuf>, format: Format) -> Self {
+        Self {
+            directory: directory.into(),
+            format,
+            palette: Palette::default(),
+            scale: 4,
+            every: 1,
+            limit: None,
+            delay: 5,
+            offered: 0,
+            skipped: false,
+            written: 0,
+            gif: None,
+        }
+    }
+
+    /// Exporter configured by command line arguments, if `--export DIRECTORY`
+    /// is given. Optional `--format png|gif`, `--scale PIXELS`, `--every N`,
+    /// `--limit FRAMES`, `--delay CENTISECONDS` and `--palette NAME=RRGGBB,...`
+    /// change the defaults.
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let value = |flag: &str| {
+            let index = args.iter().position(|arg| arg == flag)?;
+            Some(
+                args.get(index + 1)
+                    .unwrap_or_else(|| panic!("{flag} needs a value"))
+                    .as_str(),
+            )
+        };
+        let format = value("--format").map_or(Format::Png, |format| format.parse().unwrap());
+        let mut exporter = Self::new(value("--export")?, format);
+        if let Some(scale) = value("--scale") {
+            exporter = exporter.scale(scale.parse().expect("Scale should be a number"));
+        }
+        if let Some(every) = value("--every") {
+            exporter = exporter.every(every.parse().expect("--every should be a number"));
+        }
+        if let Some(limit) = value("--limit") {
+            exporter = exporter.limit(limit.parse().expect("--limit should be a number"));
+        }
+        if let Some(delay) = value("--delay") {
+            exporter = exporter.delay(delay.parse().expect("Delay should be a number"));
+        }
+        if let Some(palette) = value("--palette") {
+            exporter = exporter.palette(palette.parse().unwrap());
+        }
+        Some(exporter)
+    }
+
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn scale(mut self, scale: u16) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    pub fn every(mut self, every: usize) -> Self {
+        self.every = every.max(1);
+        self
+    }
+
+    pub fn limit(mut self, frames: usize) -> Self {
+        self.limit = Some(frames.max(1));
+        self
+    }
+
+    /// Keep fewer frames of a simulation with `total` steps, so that at most
+    /// the limit, or `default_limit` if none is set, are written in even steps
+    /// including the final frame of `finish_with`.
+    pub fn spread(mut self, total: usize, default_limit: usize) -> Self {
+        let limit = *self.limit.get_or_insert(default_limit.max(1));
+        // One frame is left for the final state
+        let kept = (limit - 1).max(1);
+        self.every = self.every.max(total.div_ceil(kept));
+        self
+    }
+
+    pub fn delay(mut self, delay: u16) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Add a frame. `draw` is only called for frames that are kept, so
+    /// skipped frames cost nothing.
+    pub fn frame<G: Grid>(&mut self, draw: impl FnOnce() -> G) -> io::Result<()> {
+        let index = self.offered;
+        self.offered += 1;
+        self.skipped = !index.is_multiple_of(self.every) || self.is_full();
+        if self.skipped {
+            return Ok(());
+        }
+        self.write(&draw())
+    }
+
+    fn write(&mut self, grid: &impl Grid) -> io::Result<()> {
+        let (width, height) = (
+            grid.width() * self.scale as usize,
+            grid.height() * self.scale as usize,
+        );
+        let (width, height) = (
+            u16::try_from(width).map_err(|_| io::Error::other("Image too wide"))?,
+            u16::try_from(height).map_err(|_| io::Error::other("Image too tall"))?,
+        );
+        let pixels = self.pixels(grid);
+        if self.written == 0 {
+            fs::create_dir_all(&self.directory)?;
+        }
+
+        match self.format {
+            Format::Png => {
+                let path = self
+                    .directory
+                    .join(format!("frame-{:05}.png", self.written));
+                let mut encoder = png::Encoder::new(
+                    BufWriter::new(File::create(path)?),
+                    width as u32,
+                    height as u32,
+                );
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(png::BitDepth::Eight);
+                let table = self.palette.table();
+                let rgb: Vec<u8> = pixels
+                    .iter()
+                    .flat_map(|&index| table[index as usize])
+                    .collect();
+                encoder
+                    .write_header()
+                    .and_then(|mut writer| writer.write_image_data(&rgb))
+                    .map_err(io::Error::other)?;
+            }
+            Format::Gif => {
+                if self.gif.is_none() {
+                    let file = BufWriter::new(File::create(self.directory.join("animation.gif"))?);
+                    let table: Vec<u8> = self.palette.table().concat();
+                    let mut encoder =
+                        gif::Encoder::new(file, width, height, &table).map_err(io::Error::other)?;
+                    encoder
+                        .set_repeat(gif::Repeat::Infinite)
+                        .map_err(io::Error::other)?;
+                    self.gif = Some(encoder);
+                }
+                let mut frame = gif::Frame {
+                    width,
+                    height,
+                    delay: self.delay,
+                    ..gif::Frame::default()
+                };
+                frame.buffer = pixels.into();
+                self.gif
+                    .as_mut()
+                    .unwrap()
+                    .write_frame(&frame)
+                    .map_err(io::Error::other)?;
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Like `finish`, but keeps the latest offered frame, drawn by `draw`,
+    /// if it was skipped
+    pub fn finish_with<G: Grid>(mut self, draw: impl FnOnce() -> G) -> io::Result<usize> {
+        if self.skipped && !self.is_full() {
+            self.write(&draw())?;
+        }
+        self.finish()
+    }
+
+    fn is_full(&self) -> bool {
+        self.limit.is_some_and(|limit| self.written >= limit)
+    }
+
+    /// Finish writing, returns the number of frames written
+    pub fn finish(self) -> io::Result<usize> {
+        if let Some(encoder) = self.gif {
+            encoder.into_inner().map_err(io::Error::other)?;
+        }
+        Ok(self.written)
+    }
+
+    /// Palette indices of the scaled image, row by row
+    fn pixels(&self, grid: &impl Grid) -> Vec<u8> {
+        let scale = self.scale as usize;
+        let mut pixels = Vec::with_capacity(grid.width() * grid.height() * scale * scale);
+        for y in 0..grid.height() {
+            let row: Vec<u8> = (0..grid.width())
+                .flat_map(|x| iter::repeat_n(self.palette.index(grid.cell(x, y)), scale))
+                .collect();
+            for _ in 0..scale {
+                pixels.extend(&row);
+            }
+        }
+        pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Canvas;
+
+    fn canvas() -> Canvas {
+        let mut canvas = Canvas::new(2, 1, Cell::EMPTY);
+        canvas.set(1, 0, Cell::new('#').fg(Colour::Red));
+        canvas
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette: Palette = "background=000000,bright_red=#ff0000".parse().unwrap();
+        assert_eq!(palette.background, [0, 0, 0]);
+        assert_eq!(palette.colours[&Colour::BrightRed], [255, 0, 0]);
+        assert_eq!(palette.foreground, Palette::default().foreground);
+        assert!("pink=ffffff".parse::<Palette>().is_err());
+        assert!("red=fff".parse::<Palette>().is_err());
+
+        let table = palette.table();
+        assert_eq!(table[palette.index(Cell::EMPTY) as usize], [0, 0, 0]);
+        assert_eq!(
+            table[palette.index(Cell::new('@')) as usize],
+            palette.foreground
+        );
+        let cell = Cell::new('.').fg(Colour::Red).bg(Colour::BrightRed);
+        assert_eq!(table[palette.index(cell) as usize], [255, 0, 0]);
+    }
+
+    #[test]
+    fn test_png() {
+        let directory = std::env::temp_dir().join(format!("render-png-{}", std::process::id()));
+        let mut exporter = Exporter::new(&directory, Format::Png).scale(3).every(2);
+        for _ in 0..3 {
+            exporter.frame(canvas).unwrap();
+        }
+        assert_eq!(exporter.finish().unwrap(), 2);
+
+        let decoder = png::Decoder::new(File::open(directory.join("frame-00001.png")).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut rgb = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut rgb).unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (6, 3));
+        let palette = Palette::default();
+        assert_eq!(rgb[0..3], palette.background);
+        assert_eq!(rgb[15..18], palette.colours[&Colour::Red]);
+
+        // The skipped final frame is kept
+        let mut exporter = Exporter::new(&directory, Format::Png).every(2);
+        exporter.frame(canvas).unwrap();
+        exporter.frame(canvas).unwrap();
+        assert_eq!(exporter.finish_with(canvas).unwrap(), 2);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_limit() {
+        let directory = std::env::temp_dir().join(format!("render-limit-{}", std::process::id()));
+        let mut exporter = Exporter::new(&directory, Format::Png).limit(2);
+        for _ in 0..5 {
+            exporter.frame(canvas).unwrap();
+        }
+        assert_eq!(exporter.finish_with(canvas).unwrap(), 2);
+
+        // 1000 steps fit in 10 frames with every 112th step and the final one
+        let mut exporter = Exporter::new(&directory, Format::Png).spread(1000, 10);
+        assert_eq!((exporter.every, exporter.limit), (112, Some(10)));
+        for _ in 0..1000 {
+            exporter.frame(canvas).unwrap();
+        }
+        assert_eq!(exporter.finish_with(canvas).unwrap(), 10);
+
+        // An explicit limit wins over the default, and a coarser `every` is kept
+        let exporter = Exporter::new(&directory, Format::Png)
+            .limit(101)
+            .spread(1000, 10);
+        assert_eq!(exporter.every, 10);
+        let exporter = Exporter::new(&directory, Format::Png)
+            .every(500)
+            .spread(1000, 10);
+        assert_eq!(exporter.every, 500);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_gif() {
+        let directory = std::env::temp_dir().join(format!("render-gif-{}", std::process::id()));
+        let mut exporter = Exporter::new(&directory, Format::Gif);
+        for _ in 0..3 {
+            exporter.frame(canvas).unwrap();
+        }
+        assert_eq!(exporter.finish().unwrap(), 3);
+
+        let mut decoder = gif::DecodeOptions::new()
+            .read_info(File::open(directory.join("animation.gif")).unwrap())
+            .unwrap();
+        let mut frames = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            frames += 1;
+        }
+        assert_eq!(frames, 3);
+        assert_eq!((decoder.width(), decoder.height()), (8, 4));
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_from_args() {
+        let args: Vec<String> = [
+            "day", "--export", "out", "--format", "gif", "--every", "10", "--limit", "50",
+        ]
+        .map(String::from)
+        .into();
+        let exporter = Exporter::from_args(&args).unwrap();
+        assert_eq!(exporter.directory, PathBuf::from("out"));
+        assert_eq!((exporter.format, exporter.every), (Format::Gif, 10));
+        assert_eq!(exporter.limit, Some(50));
+        assert!(Exporter::from_args(&args[..1]).is_none());
+    }
+}
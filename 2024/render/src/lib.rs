@@ -0,0 +1,222 @@
+//! Grids of coloured cells shared by the simulation days, and ways to show them.
+
+mod debugger;
+#[cfg(feature = "export")]
+mod export;
+mod terminal;
+
+pub use debugger::{Debugger, Simulation};
+#[cfg(feature = "export")]
+pub use export::{Exporter, Format, Palette};
+pub use terminal::Renderer;
+
+/// The standard terminal colours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Colour {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Grey,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl Colour {
+    /// Distinguishable colours for labelling many things, e.g. regions
+    pub const PALETTE: [Colour; 12] = [
+        Colour::Red,
+        Colour::Green,
+        Colour::Yellow,
+        Colour::Blue,
+        Colour::Magenta,
+        Colour::Cyan,
+        Colour::BrightRed,
+        Colour::BrightGreen,
+        Colour::BrightYellow,
+        Colour::BrightBlue,
+        Colour::BrightMagenta,
+        Colour::BrightCyan,
+    ];
+
+    pub const ALL: [Colour; 16] = [
+        Colour::Black,
+        Colour::Red,
+        Colour::Green,
+        Colour::Yellow,
+        Colour::Blue,
+        Colour::Magenta,
+        Colour::Cyan,
+        Colour::White,
+        Colour::Grey,
+        Colour::BrightRed,
+        Colour::BrightGreen,
+        Colour::BrightYellow,
+        Colour::BrightBlue,
+        Colour::BrightMagenta,
+        Colour::BrightCyan,
+        Colour::BrightWhite,
+    ];
+
+    /// Colour number `n` of the palette, wrapping around
+    pub fn nth(n: usize) -> Self {
+        Self::PALETTE[n % Self::PALETTE.len()]
+    }
+
+    /// SGR code of the colour as foreground, add 10 for background
+    fn ansi(&self) -> u8 {
+        match self {
+            Colour::Black => 30,
+            Colour::Red => 31,
+            Colour::Green => 32,
+            Colour::Yellow => 33,
+            Colour::Blue => 34,
+            Colour::Magenta => 35,
+            Colour::Cyan => 36,
+            Colour::White => 37,
+            Colour::Grey => 90,
+            Colour::BrightRed => 91,
+            Colour::BrightGreen => 92,
+            Colour::BrightYellow => 93,
+            Colour::BrightBlue => 94,
+            Colour::BrightMagenta => 95,
+            Colour::BrightCyan => 96,
+            Colour::BrightWhite => 97,
+        }
+    }
+}
+
+/// A single character of a grid with its colours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cell {
+    pub symbol: char,
+    pub foreground: Option<Colour>,
+    pub background: Option<Colour>,
+}
+
+impl Cell {
+    pub const EMPTY: Self = Cell::new('.');
+
+    /// Whether the cell shows nothing but its background
+    pub fn is_blank(&self) -> bool {
+        matches!(self.symbol, '.' | ' ')
+    }
+
+    pub const fn new(symbol: char) -> Self {
+        Self {
+            symbol,
+            foreground: None,
+            background: None,
+        }
+    }
+
+    pub const fn fg(mut self, colour: Colour) -> Self {
+        self.foreground = Some(colour);
+        self
+    }
+
+    pub const fn bg(mut self, colour: Colour) -> Self {
+        self.background = Some(colour);
+        self
+    }
+}
+
+/// Anything that can be drawn as a rectangle of cells.
+pub trait Grid {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    /// Cell at column `x` of row `y`, both within bounds
+    fn cell(&self, x: usize, y: usize) -> Cell;
+}
+
+/// Grid that the days paint their state onto.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize, fill: Cell) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Canvas of the lines of `text`, styled per character. Short lines are
+    /// padded with spaces.
+    pub fn from_text(text: &str, style: impl Fn(char) -> Cell) -> Self {
+        let width = text.lines().map(|line| line.chars().count()).max();
+        let mut canvas = Self::new(width.unwrap_or(0), text.lines().count(), Cell::new(' '));
+        for (y, line) in text.lines().enumerate() {
+            for (x, symbol) in line.chars().enumerate() {
+                canvas.set(x, y, style(symbol));
+            }
+        }
+        canvas
+    }
+
+    /// Replace the cell at (`x`, `y`), ignored if out of bounds
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = cell;
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Cell> {
+        (x < self.width && y < self.height).then(|| self.cells[y * self.width + x])
+    }
+}
+
+impl Grid for Canvas {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Cell {
+        self.cells[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canvas() {
+        let mut canvas = Canvas::from_text("#.\n#", |symbol| match symbol {
+            '#' => Cell::new('#').fg(Colour::Grey),
+            symbol => Cell::new(symbol),
+        });
+        assert_eq!((canvas.width(), canvas.height()), (2, 2));
+        assert_eq!(canvas.get(0, 1), Some(Cell::new('#').fg(Colour::Grey)));
+        assert_eq!(canvas.get(1, 1), Some(Cell::new(' ')));
+        assert_eq!(canvas.get(2, 0), None);
+
+        canvas.set(1, 0, Cell::new('@'));
+        canvas.set(5, 5, Cell::new('@'));
+        assert_eq!(canvas.cell(1, 0), Cell::new('@'));
+    }
+
+    #[test]
+    fn test_nth_colour() {
+        assert_eq!(Colour::nth(0), Colour::Red);
+        assert_eq!(Colour::nth(Colour::PALETTE.len() + 1), Colour::Green);
+    }
+}
@@ -0,0 +1,145 @@
+//! Drawing grids to the terminal with ANSI escape codes.
+
+use std::{
+    env,
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+use crate::{Cell, Colour, Grid};
+
+const RESET: &str = "\x1B[0m";
+const CLEAR: &str = "\x1B[2J\x1B[H";
+
+/// Draws grids followed by a legend and a status line.
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    legend: Vec<(Cell, String)>,
+    colour: bool,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer {
+    /// Colours are used unless the `NO_COLOR` environment variable is set.
+    pub fn new() -> Self {
+        Self {
+            legend: Vec::new(),
+            colour: env::var_os("NO_COLOR").is_none(),
+        }
+    }
+
+    /// Draw symbols only, without escape codes
+    pub fn plain(mut self) -> Self {
+        self.colour = false;
+        self
+    }
+
+    /// Explain what `cell` means below the grid
+    pub fn legend(mut self, cell: Cell, meaning: &str) -> Self {
+        self.legend.push((cell, meaning.to_string()));
+        self
+    }
+
+    /// The grid, legend and `status` as text. An empty status is left out.
+    pub fn render(&self, grid: &impl Grid, status: &str) -> String {
+        let mut output = String::new();
+        for y in 0..grid.height() {
+            let mut style = None;
+            for x in 0..grid.width() {
+                let cell = grid.cell(x, y);
+                self.style(&mut output, &mut style, cell);
+                output.push(cell.symbol);
+            }
+            self.style(&mut output, &mut style, Cell::EMPTY);
+            output.push('\n');
+        }
+
+        if !self.legend.is_empty() {
+            let mut style = None;
+            for (n, &(cell, ref meaning)) in self.legend.iter().enumerate() {
+                if n > 0 {
+                    output.push_str("  ");
+                }
+                self.style(&mut output, &mut style, cell);
+                output.push(cell.symbol);
+                self.style(&mut output, &mut style, Cell::EMPTY);
+                write!(output, " {meaning}").unwrap();
+            }
+            output.push('\n');
+        }
+        if !status.is_empty() {
+            writeln!(output, "{status}").unwrap();
+        }
+        output
+    }
+
+    /// Clear the terminal and draw the grid at the top
+    pub fn draw(&self, grid: &impl Grid, status: &str) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        if self.colour {
+            stdout.write_all(CLEAR.as_bytes())?;
+        }
+        stdout.write_all(self.render(grid, status).as_bytes())?;
+        stdout.flush()
+    }
+
+    /// Switch to the colours of `cell` if they differ from the current `style`
+    fn style(
+        &self,
+        output: &mut String,
+        style: &mut Option<(Option<Colour>, Option<Colour>)>,
+        cell: Cell,
+    ) {
+        let next = (cell.foreground, cell.background);
+        if !self.colour || *style == Some(next) || (style.is_none() && next == (None, None)) {
+            return;
+        }
+        output.push_str(RESET);
+        if let Some(foreground) = cell.foreground {
+            write!(output, "\x1B[{}m", foreground.ansi()).unwrap();
+        }
+        if let Some(background) = cell.background {
+            write!(output, "\x1B[{}m", background.ansi() + 10).unwrap();
+        }
+        *style = Some(next);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Canvas;
+
+    #[test]
+    fn test_plain() {
+        let canvas = Canvas::from_text("#@\n..", |symbol| Cell::new(symbol).fg(Colour::Red));
+        let renderer = Renderer::new()
+            .plain()
+            .legend(Cell::new('@').fg(Colour::Red), "robot");
+        assert_eq!(
+            renderer.render(&canvas, "Step 1"),
+            "#@\n..\n@ robot\nStep 1\n"
+        );
+        assert_eq!(Renderer::new().plain().render(&canvas, ""), "#@\n..\n");
+    }
+
+    #[test]
+    fn test_colours() {
+        let mut canvas = Canvas::new(3, 1, Cell::EMPTY);
+        canvas.set(1, 0, Cell::new('#').fg(Colour::Grey));
+        canvas.set(2, 0, Cell::new('#').fg(Colour::Grey).bg(Colour::Blue));
+        let renderer = Renderer {
+            legend: Vec::new(),
+            colour: true,
+        };
+        assert_eq!(
+            renderer.render(&canvas, ""),
+            ".\x1B[0m\x1B[90m#\x1B[0m\x1B[90m\x1B[44m#\x1B[0m\n"
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! The `--threads N` flag of the days with a `parallel` feature.
+
+/// Size the thread pool from `--threads N`, every core by default. Has no
+/// effect without the `parallel` feature.
+pub fn configure(args: &[String]) {
+    let Some(index) = args.iter().position(|arg| arg == "--threads") else {
+        return;
+    };
+    let threads: usize = args
+        .get(index + 1)
+        .and_then(|threads| threads.parse().ok())
+        .expect("Thread count should be a number");
+    #[cfg(feature = "parallel")]
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .expect("Thread pool should only be configured once");
+    #[cfg(not(feature = "parallel"))]
+    eprintln!("Ignoring --threads {threads}, built without the parallel feature");
+}